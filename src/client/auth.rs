@@ -0,0 +1,157 @@
+use super::{ClientError, AwsSigner, REDACTED, aws_signer};
+
+use config::{AuthType, ElasticSearchServer, SecretsReader, Credentials};
+use chrono::Utc;
+use reqwest::{RequestBuilder, Url};
+use reqwest::header::AUTHORIZATION;
+
+/// Authentication of every request: the `Authorization` header or the AWS signature
+pub struct RequestAuth {
+    authorization: Option<String>,
+    signer: Option<AwsSigner>
+}
+
+impl RequestAuth {
+    pub fn create(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<Self, ClientError> {
+        Ok(RequestAuth {
+            authorization: authorization(server, secrets)?,
+            signer: aws_signer(server, secrets)?
+        })
+    }
+
+    pub fn apply(&self, mut request: RequestBuilder, method: &str, url: &Url, body: &[u8]) -> RequestBuilder {
+        if let Some(authorization) = &self.authorization {
+            request = request.header(AUTHORIZATION, authorization.as_str());
+        }
+        // the signature includes the time, so every retry is signed again
+        if let Some(signer) = &self.signer {
+            for (name, value) in signer.sign(method, url, body, Utc::now()) {
+                request = request.header(name.as_str(), value.as_str());
+            }
+        }
+        request
+    }
+
+    /// Headers of the authentication printed by `--dry-run`, only the schemes are kept
+    pub fn redacted(&self, method: &str, url: &Url, body: &[u8]) -> Vec<(String, String)> {
+        let redact = |value: &str| match value.find(' ') {
+            Some(position) => format!("{} {}", &value[..position], REDACTED),
+            None => REDACTED.to_string()
+        };
+        let mut headers = vec![];
+        if let Some(authorization) = &self.authorization {
+            headers.push((AUTHORIZATION.to_string(), redact(authorization)));
+        }
+        if let Some(signer) = &self.signer {
+            headers.extend(signer.sign(method, url, body, Utc::now()).into_iter()
+                .map(|(name, value)| match name.as_str() {
+                    "authorization" => (name, redact(&value)),
+                    "x-amz-security-token" => (name, REDACTED.to_string()),
+                    _ => (name, value)
+                }));
+        }
+        headers
+    }
+}
+
+/// Value of the `Authorization` header for the server, the secrets are read from the keyring
+pub fn authorization(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<Option<String>, ClientError> {
+    match server.auth_type() {
+        // the requests are signed by the fetchers
+        AuthType::None | AuthType::AwsSigV4(_) => Ok(None),
+        AuthType::Basic => {
+            let username = server.username.as_ref().ok_or_else(|| {
+                ClientError::RequestError { inner: format!("username is not set for the server {}", server.name) }
+            })?;
+            let credentials = secrets.get_credentials(username).map_err(|err| {
+                error!("Cannot read credentials: {}", err);
+                ClientError::RequestError { inner: format!("cannot read credentials: {}", err) }
+            })?;
+            Ok(credentials.map(|Credentials { username, password }| {
+                format!("Basic {}", base64::encode(&format!("{}:{}", username, password)))
+            }))
+        },
+        auth_type => {
+            let key = server.token_key();
+            let token = secrets.read(&key)
+                .map_err(|err| {
+                    error!("Cannot read {} token: {}", auth_type, err);
+                    ClientError::RequestError { inner: format!("cannot read {} token: {}", auth_type, err) }
+                })?
+                .ok_or_else(|| ClientError::RequestError {
+                    inner: format!("{} token of the server {} is not found in the keyring, use config update server {} --auth-type {}",
+                                   auth_type, server.name, server.name, auth_type)
+                })?;
+            Ok(Some(match auth_type {
+                // the key created by the API is either encoded or the id and the key separated by the colon
+                AuthType::ApiKey if token.contains(':') => format!("ApiKey {}", base64::encode(&token)),
+                AuthType::ApiKey => format!("ApiKey {}", token),
+                _ => format!("Bearer {}", token)
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::ReadSecretError;
+
+    use std::collections::HashMap;
+
+    struct TestSecrets(HashMap<&'static str, &'static str>);
+
+    impl SecretsReader for TestSecrets {
+        fn read(&self, key: &str) -> Result<Option<String>, ReadSecretError> {
+            Ok(self.0.get(key).map(ToString::to_string))
+        }
+
+        fn get_credentials(&self, username: &str) -> Result<Option<Credentials>, ReadSecretError> {
+            Ok(self.read(username)?.map(|password| Credentials { username: username.to_string(), password }))
+        }
+    }
+
+    fn server(username: Option<&str>, auth: Option<AuthType>) -> ElasticSearchServer {
+        ElasticSearchServer {
+            name: "test".to_string(),
+            server: "address".to_string(),
+            username: username.map(ToString::to_string),
+            auth,
+            ..ElasticSearchServer::default()
+        }
+    }
+
+    fn secrets() -> TestSecrets {
+        TestSecrets(vec![
+            ("user", "password"),
+            ("test/api_key", "id:key"),
+            ("test/bearer", "token")
+        ].into_iter().collect())
+    }
+
+    #[test]
+    fn it_should_use_basic_auth_for_username_without_auth_section() {
+        assert_eq!(Some("Basic dXNlcjpwYXNzd29yZA==".to_string()), authorization(&server(Some("user"), None), &secrets()).unwrap());
+        assert_eq!(None, authorization(&server(None, None), &secrets()).unwrap());
+        assert_eq!(None, authorization(&server(Some("user"), Some(AuthType::None)), &secrets()).unwrap());
+    }
+
+    #[test]
+    fn it_should_read_tokens_by_server_name() {
+        assert_eq!(Some("ApiKey aWQ6a2V5".to_string()), authorization(&server(None, Some(AuthType::ApiKey)), &secrets()).unwrap());
+        assert_eq!(Some("Bearer token".to_string()), authorization(&server(None, Some(AuthType::Bearer)), &secrets()).unwrap());
+    }
+
+    #[test]
+    fn it_should_fail_without_token() {
+        let secrets = TestSecrets(HashMap::new());
+        assert!(authorization(&server(None, Some(AuthType::Bearer)), &secrets).is_err());
+    }
+
+    #[test]
+    fn it_should_redact_authorization() {
+        let auth = RequestAuth::create(&server(Some("user"), None), &secrets()).unwrap();
+        let url = Url::parse("http://localhost:9200/_search").unwrap();
+        assert_eq!(vec![("authorization".to_string(), "Basic <redacted>".to_string())], auth.redacted("POST", &url, b"{}"));
+    }
+}
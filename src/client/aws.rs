@@ -0,0 +1,186 @@
+use super::{ClientError, AwsSigner};
+
+use config::{AuthType, AwsSigV4Config, ElasticSearchServer, SecretsReader};
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const DEFAULT_PROFILE: &str = "default";
+
+/// Access key of AWS, the session token is set for the temporary credentials
+#[derive(Clone, Debug, PartialEq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>
+}
+
+impl AwsCredentials {
+    /// Credentials stored in the keyring as `access_key_id:secret_access_key[:session_token]`
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(access_key_id), Some(secret_access_key), session_token) if !access_key_id.is_empty() && !secret_access_key.is_empty() => {
+                Some(AwsCredentials {
+                    access_key_id: access_key_id.to_string(),
+                    secret_access_key: secret_access_key.to_string(),
+                    session_token: session_token.filter(|token| !token.is_empty()).map(ToString::to_string)
+                })
+            },
+            _ => None
+        }
+    }
+}
+
+/// Signer of the requests to the server, if the server uses the `aws_sigv4` auth
+pub fn aws_signer(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<Option<AwsSigner>, ClientError> {
+    let aws = match server.auth_type() {
+        AuthType::AwsSigV4(aws) => aws,
+        _ => return Ok(None)
+    };
+    // the keyring is optional, the credentials of the machines without it are in the environment or the profile files
+    let stored = secrets.read(&server.token_key())
+        .unwrap_or_else(|err| {
+            warn!("Cannot read AWS credentials from the keyring: {}", err);
+            None
+        });
+    let credentials = resolve_credentials(&aws, stored, |name| env::var(name).ok(), read_profile_files)?;
+    Ok(Some(AwsSigner::new(credentials, &aws.region, &aws.service)))
+}
+
+/// Credentials of the configured profile, otherwise the ones from the keyring, the environment variables
+/// or the profile from `AWS_PROFILE`, like the AWS CLI does
+fn resolve_credentials<E, F>(aws: &AwsSigV4Config, stored: Option<String>, env: E, profile_files: F) -> Result<AwsCredentials, ClientError>
+    where E: Fn(&str) -> Option<String>, F: Fn(&Fn(&str) -> Option<String>) -> Vec<String>
+{
+    let invalid = |source: &str| ClientError::RequestError {
+        inner: format!("invalid AWS credentials in {}", source)
+    };
+    if aws.profile.is_none() {
+        if let Some(stored) = stored {
+            return AwsCredentials::parse(&stored).ok_or_else(|| invalid("the keyring"));
+        }
+        if let Some(access_key_id) = env("AWS_ACCESS_KEY_ID").filter(|value| !value.is_empty()) {
+            let secret_access_key = env("AWS_SECRET_ACCESS_KEY").filter(|value| !value.is_empty())
+                .ok_or_else(|| invalid("the environment, AWS_SECRET_ACCESS_KEY is not set"))?;
+            return Ok(AwsCredentials {
+                access_key_id,
+                secret_access_key,
+                session_token: env("AWS_SESSION_TOKEN").filter(|value| !value.is_empty())
+            });
+        }
+    }
+
+    let profile = aws.profile.clone()
+        .or_else(|| env("AWS_PROFILE").filter(|value| !value.is_empty()))
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    // the credentials file takes precedence over the config file
+    profile_files(&env).iter()
+        .filter_map(|content| parse_profile(content, &profile))
+        .find(|properties| properties.contains_key("aws_access_key_id"))
+        .map(|properties| {
+            AwsCredentials::parse(&format!(
+                "{}:{}:{}",
+                properties["aws_access_key_id"],
+                properties.get("aws_secret_access_key").map(String::as_str).unwrap_or_default(),
+                properties.get("aws_session_token").map(String::as_str).unwrap_or_default()
+            )).ok_or_else(|| invalid(&format!("the profile {}", profile)))
+        })
+        .unwrap_or_else(|| Err(ClientError::RequestError {
+            inner: format!("AWS credentials are not found in the keyring, the environment variables or the profile {}", profile)
+        }))
+}
+
+/// Content of the shared credentials file and the config file
+fn read_profile_files(env: &Fn(&str) -> Option<String>) -> Vec<String> {
+    let home = dirs::home_dir().unwrap_or_default().join(".aws");
+    let credentials = env("AWS_SHARED_CREDENTIALS_FILE").map(PathBuf::from).unwrap_or_else(|| home.join("credentials"));
+    let config = env("AWS_CONFIG_FILE").map(PathBuf::from).unwrap_or_else(|| home.join("config"));
+    vec![credentials, config].into_iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .collect()
+}
+
+/// Properties of the profile in the INI file, sections of the config file are prefixed with `profile`
+fn parse_profile(content: &str, profile: &str) -> Option<HashMap<String, String>> {
+    let mut properties = None;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with(';') || line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            if properties.is_some() {
+                break;
+            }
+            let section = line[1..line.len() - 1].trim();
+            let name = section.strip_prefix("profile ").map(str::trim).unwrap_or(section);
+            if name == profile {
+                properties = Some(HashMap::new());
+            }
+        } else if let Some(properties) = properties.as_mut() {
+            if let Some(position) = line.find('=') {
+                properties.insert(line[..position].trim().to_lowercase(), line[position + 1..].trim().to_string());
+            }
+        }
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CREDENTIALS: &str = "[default]\naws_access_key_id = AKIDDEFAULT\naws_secret_access_key = default/secret\n\n\
+                               [logs]\n# temporary\naws_access_key_id=AKIDLOGS\naws_secret_access_key=logs/secret\naws_session_token=session\n";
+    const CONFIG: &str = "[profile audit]\nregion = eu-west-1\naws_access_key_id = AKIDAUDIT\naws_secret_access_key = audit/secret\n";
+
+    fn aws(profile: Option<&str>) -> AwsSigV4Config {
+        AwsSigV4Config { region: "eu-west-1".to_string(), service: "es".to_string(), profile: profile.map(ToString::to_string) }
+    }
+
+    fn env(variables: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| variables.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+    }
+
+    fn files(_: &Fn(&str) -> Option<String>) -> Vec<String> {
+        vec![CREDENTIALS.to_string(), CONFIG.to_string()]
+    }
+
+    fn credentials(access_key_id: &str, secret_access_key: &str, session_token: Option<&str>) -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            session_token: session_token.map(ToString::to_string)
+        }
+    }
+
+    #[test]
+    fn it_should_prefer_keyring_then_environment() {
+        let environment = env(&[("AWS_ACCESS_KEY_ID", "AKIDENV"), ("AWS_SECRET_ACCESS_KEY", "env/secret"), ("AWS_SESSION_TOKEN", "token")]);
+        assert_eq!(credentials("AKIDSTORED", "stored/secret", None),
+                   resolve_credentials(&aws(None), Some("AKIDSTORED:stored/secret".to_string()), &environment, files).unwrap());
+        assert_eq!(credentials("AKIDENV", "env/secret", Some("token")),
+                   resolve_credentials(&aws(None), None, &environment, files).unwrap());
+    }
+
+    #[test]
+    fn it_should_read_profiles_from_files() {
+        assert_eq!(credentials("AKIDDEFAULT", "default/secret", None),
+                   resolve_credentials(&aws(None), None, env(&[]), files).unwrap());
+        assert_eq!(credentials("AKIDLOGS", "logs/secret", Some("session")),
+                   resolve_credentials(&aws(None), None, env(&[("AWS_PROFILE", "logs")]), files).unwrap());
+        // the configured profile overrides the keyring and the environment
+        let environment = env(&[("AWS_ACCESS_KEY_ID", "AKIDENV"), ("AWS_SECRET_ACCESS_KEY", "env/secret")]);
+        assert_eq!(credentials("AKIDAUDIT", "audit/secret", None),
+                   resolve_credentials(&aws(Some("audit")), Some("AKIDSTORED:stored/secret".to_string()), environment, files).unwrap());
+    }
+
+    #[test]
+    fn it_should_fail_without_credentials() {
+        assert!(resolve_credentials(&aws(Some("missing")), None, env(&[]), files).is_err());
+        assert!(resolve_credentials(&aws(None), None, env(&[("AWS_ACCESS_KEY_ID", "AKIDENV")]), files).is_err());
+        assert!(resolve_credentials(&aws(None), Some("AKIDSTORED".to_string()), env(&[]), files).is_err());
+    }
+}
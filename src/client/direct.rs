@@ -0,0 +1,62 @@
+use super::{ApiRequest, ClientError, Connection, FetcherError, PreparedRequest, Transport};
+
+use config::{ElasticSearchServer, SecretsReader};
+use reqwest::Url;
+use serde_json::Value;
+
+/// Requests sent to the API of Elasticsearch or OpenSearch directly
+pub struct DirectTransport {
+    connection: Connection,
+    address: Url
+}
+
+impl DirectTransport {
+    pub fn create(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<Self, ClientError> {
+        let address = server.address()?;
+        let address = Url::parse(&address).map_err(|err| {
+            error!("Invalid server address: {}", err);
+            ClientError::RequestError { inner: format!("invalid server address: {}", err) }
+        })?;
+        if address.cannot_be_a_base() {
+            return Err(ClientError::RequestError { inner: format!("invalid server address: {}", address) });
+        }
+        Ok(DirectTransport { connection: Connection::create(server, secrets)?, address })
+    }
+
+    /// URL of the API request, the server may be behind the reverse proxy with the path prefix
+    pub fn url(address: &Url, request: &ApiRequest) -> Url {
+        let mut url = address.clone();
+        url.set_path(&format!("{}{}", address.path().trim_end_matches('/'), request.encoded_path()));
+        url
+    }
+}
+
+impl Transport for DirectTransport {
+    fn product(&self) -> &'static str {
+        self.connection.product()
+    }
+
+    fn send(&self, request: &ApiRequest) -> Result<Value, FetcherError> {
+        let url = Self::url(&self.address, request);
+        self.connection.send(request, request.method.clone(), &url, &[])
+    }
+
+    fn prepare(&self, request: &ApiRequest) -> PreparedRequest {
+        let url = Self::url(&self.address, request);
+        self.connection.prepare(request, request.method.clone(), &url, &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_keep_path_prefix_of_address() {
+        let search = ApiRequest::post(&["logs-*", "_search"], json!({}));
+        let url = |address: &str| DirectTransport::url(&Url::parse(address).unwrap(), &search).to_string();
+        assert_eq!("http://localhost:9200/logs-*/_search", url("http://localhost:9200"));
+        assert_eq!("https://example.com/elasticsearch/logs-*/_search", url("https://example.com/elasticsearch/"));
+        assert_eq!("https://example.com/elasticsearch/", DirectTransport::url(&Url::parse("https://example.com/elasticsearch").unwrap(), &ApiRequest::get(&[])).to_string());
+    }
+}
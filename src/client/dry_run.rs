@@ -0,0 +1,77 @@
+use super::ApiRequest;
+
+use reqwest::{Method, Url};
+
+/// Placeholder of the secrets in the printed requests
+pub const REDACTED: &str = "<redacted>";
+
+/// HTTP request printed by `--dry-run` instead of being sent, the secrets are redacted
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreparedRequest {
+    /// The API request, the console proxy sends it by another method and URL
+    pub api: ApiRequest,
+    pub method: Method,
+    pub url: Url,
+    pub headers: Vec<(String, String)>
+}
+
+impl PreparedRequest {
+    /// Command of curl which sends the request, the body is the compact JSON
+    pub fn curl(&self) -> String {
+        let mut lines = vec![format!("curl -X {} {}", self.method, shell_quote(self.url.as_str()))];
+        lines.extend(self.headers.iter().map(|(name, value)| format!("  -H {}", shell_quote(&format!("{}: {}", name, value)))));
+        if let Some(body) = &self.api.body {
+            lines.push(format!("  -d {}", shell_quote(&body.to_string())));
+        }
+        lines.join(" \\\n")
+    }
+
+    /// Request in the syntax of the Dev Tools console of Kibana, the path is relative to the Elasticsearch server
+    pub fn console(&self) -> String {
+        let path = self.api.encoded_path();
+        let path = if path == "/" { path.as_str() } else { path.trim_start_matches('/') };
+        let request = format!("{} {}", self.api.method, path);
+        match &self.api.body {
+            Some(body) => format!("{}\n{}", request, serde_json::to_string_pretty(body).unwrap_or_else(|_| body.to_string())),
+            None => request
+        }
+    }
+}
+
+/// Value in the single quotes of the POSIX shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepared(api: ApiRequest) -> PreparedRequest {
+        PreparedRequest {
+            method: Method::POST,
+            url: Url::parse("https://kibana.example.com/api/console/proxy?method=POST&path=%2Flogs-*%2F_search").unwrap(),
+            headers: vec![("authorization".to_string(), format!("Basic {}", REDACTED)), ("kbn-xsrf".to_string(), "reporting".to_string())],
+            api
+        }
+    }
+
+    #[test]
+    fn it_should_print_curl_command() {
+        let request = prepared(ApiRequest::post(&["logs-*", "_search"], json!({ "query": { "query_string": { "query": "user:'bob'" } } })));
+        assert_eq!(
+            "curl -X POST 'https://kibana.example.com/api/console/proxy?method=POST&path=%2Flogs-*%2F_search' \\\n  \
+             -H 'authorization: Basic <redacted>' \\\n  \
+             -H 'kbn-xsrf: reporting' \\\n  \
+             -d '{\"query\":{\"query_string\":{\"query\":\"user:'\\''bob'\\''\"}}}'",
+            request.curl()
+        );
+    }
+
+    #[test]
+    fn it_should_print_console_request() {
+        let request = prepared(ApiRequest::post(&["logs-*", "_search"], json!({ "size": 10 })));
+        assert_eq!("POST logs-*/_search\n{\n  \"size\": 10\n}", request.console());
+        assert_eq!("GET /", prepared(ApiRequest::get(&[])).console());
+    }
+}
@@ -1,27 +1,60 @@
+use super::TotalRelation;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 #[derive(Debug, Fail)]
 pub enum FetcherError {
     #[fail(display = "{}", inner)]
     RequestError { inner: String }
 }
 
-pub trait Fetcher<T> {
-    fn fetch_next(&self, from: usize) -> Result<(usize, Vec<T>), FetcherError>;
+/// Count of the matched items, the servers may stop counting at the limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Total {
+    pub value: usize,
+    pub relation: TotalRelation
+}
+
+impl From<usize> for Total {
+    fn from(value: usize) -> Self {
+        Total { value, relation: TotalRelation::Equal }
+    }
+}
+
+/// Fetches the pages, the pages may be fetched in the background thread
+pub trait Fetcher<T>: Send + Sync {
+    fn fetch_next(&self, from: usize) -> Result<(Total, Vec<T>), FetcherError>;
+
+    /// Fetches the items sorted after the fetched ones for the follow mode, the next page by default
+    fn fetch_new(&self, from: usize) -> Result<(Total, Vec<T>), FetcherError> {
+        self.fetch_next(from)
+    }
 }
 
 pub struct Collector<T> where T: Clone {
-    fetcher: Box<Fetcher<T>>,
+    fetcher: Arc<Fetcher<T>>,
     buffer: Vec<T>,
     pub from: usize,
     pub total: usize,
+    /// Whether the total is exact or the lower bound
+    pub total_relation: TotalRelation,
+    /// Duration of the last successful request
+    pub latency: Option<Duration>,
+    /// Error which stopped the iteration, the results are incomplete
+    pub failure: Option<FetcherError>
 }
 
 impl <T> Collector<T> where T: Clone {
     pub fn create(fetcher: impl Fetcher<T> + 'static) -> Result<Self, FetcherError> {
         let mut collector = Collector {
-            fetcher: Box::new(fetcher),
+            fetcher: Arc::new(fetcher),
             buffer: Vec::new(),
             from: 0,
-            total: 0
+            total: 0,
+            total_relation: TotalRelation::Equal,
+            latency: None,
+            failure: None
         };
 
         collector.fetch_next(true)
@@ -35,33 +68,103 @@ impl <T> Collector<T> where T: Clone {
         }
     }
 
+    /// Returns the item only if it has been loaded already
+    pub fn get_loaded(&self, index: usize) -> Option<&T> {
+        self.buffer.get(index)
+    }
+
+    pub fn has_more(&self) -> bool {
+        self.from < self.total
+    }
+
+    /// Fetches the next page, returns count of the loaded items
+    pub fn load_next(&mut self) -> Result<usize, FetcherError> {
+        self.fetch_next(false)
+    }
+
     fn get(&mut self, index: usize) -> Option<&T> {
         // no more fetched items
-        if self.from - index == 0 &&
-            self.fetch_next(false).unwrap_or(0) == 0 {
-            return None
+        if self.from - index == 0 {
+            match self.load_next() {
+                Ok(0) => return None,
+                Ok(_) => self.failure = None,
+                Err(err) => {
+                    self.failure = Some(err);
+                    return None
+                }
+            }
         }
 
         self.buffer.get(index)
     }
 
-    fn fetch_next(&mut self, first: bool) -> Result<usize, FetcherError> {
-        if !first && self.from >= self.total {
+    /// Next page, None if all items are loaded
+    pub fn next_page(&self) -> Option<PageRequest<T>> {
+        if self.has_more() {
+            Some(PageRequest { fetcher: self.fetcher.clone(), from: self.from, new: false })
+        } else {
+            None
+        }
+    }
+
+    /// Page of the items which appeared after the loaded ones, even if all items were loaded
+    pub fn new_page(&self) -> PageRequest<T> {
+        PageRequest { fetcher: self.fetcher.clone(), from: self.from, new: true }
+    }
+
+    /// Adds the page fetched by the request of `next_page` or `new_page`, returns count of the loaded items.
+    /// The page requested before other items were added is dropped, it would repeat them.
+    pub fn add_page(&mut self, page: Page<T>) -> Result<usize, FetcherError> {
+        if page.from != self.from {
             return Ok(0)
         }
-        let (total, results) = self.fetcher.fetch_next(self.from)?;
+        let (total, results) = page.result?;
+        self.latency = Some(page.latency);
         if results.is_empty() {
             return Ok(0)
         }
         let count = results.len();
-        info!("Loaded {}/{} results", self.from + count, total);
+        info!("Loaded {}/{} results", self.from + count, total.value);
 
-        self.total = total;
+        self.total = total.value;
+        self.total_relation = total.relation;
         self.from += results.len();
         self.buffer.extend(results);
 
         Ok(count)
     }
+
+    fn fetch_next(&mut self, first: bool) -> Result<usize, FetcherError> {
+        if !first && self.from >= self.total {
+            return Ok(0)
+        }
+        let page = PageRequest { fetcher: self.fetcher.clone(), from: self.from, new: false }.send();
+        self.add_page(page)
+    }
+}
+
+/// Request of the next page which is sent without borrowing the collector, e.g. in another thread.
+/// The fetched page is added by `Collector::add_page`.
+pub struct PageRequest<T> {
+    fetcher: Arc<Fetcher<T>>,
+    from: usize,
+    /// Fetches the new items for the follow mode
+    new: bool
+}
+
+/// Fetched page with the duration of its request
+pub struct Page<T> {
+    result: Result<(Total, Vec<T>), FetcherError>,
+    latency: Duration,
+    from: usize
+}
+
+impl <T> PageRequest<T> {
+    pub fn send(self) -> Page<T> {
+        let started = Instant::now();
+        let result = if self.new { self.fetcher.fetch_new(self.from) } else { self.fetcher.fetch_next(self.from) };
+        Page { result, latency: started.elapsed(), from: self.from }
+    }
 }
 
 pub struct CollectorIterator<'a, T> where T: Clone {
@@ -90,13 +193,37 @@ impl <'a, T> DoubleEndedIterator for CollectorIterator<'a, T> where T: Clone {
 mod tests {
     use super::*;
 
-    struct FnFetcher<T>(pub Box<Fn(usize) -> Result<(usize, Vec<T>), FetcherError>>);
+    struct FnFetcher<T>(pub Box<Fn(usize) -> Result<(usize, Vec<T>), FetcherError> + Send + Sync>);
     impl <T> Fetcher<T> for FnFetcher<T> {
-        fn fetch_next(&self, from: usize) -> Result<(usize, Vec<T>), FetcherError> {
-            (self.0)(from)
+        fn fetch_next(&self, from: usize) -> Result<(Total, Vec<T>), FetcherError> {
+            (self.0)(from).map(|(total, items)| (total.into(), items))
         }
     }
 
+    #[test]
+    fn it_should_load_new_items_after_all_items_were_loaded() {
+        let fetcher = FnFetcher(Box::new(|from| match from {
+            0 => Ok((1, vec![1])),
+            _ => Ok((3, vec![2, 3])),
+        }));
+        let mut collector = Collector::create(fetcher).unwrap();
+        assert_eq!(0, collector.load_next().unwrap());
+        let page = collector.new_page().send();
+        assert_eq!(2, collector.add_page(page).unwrap());
+        assert_eq!(3, collector.total);
+        assert_eq!(Some(&3), collector.get_loaded(2));
+    }
+
+    #[test]
+    fn it_should_drop_page_requested_before_other_items_were_added() {
+        let fetcher = FnFetcher(Box::new(|from| Ok((4, vec![from + 1, from + 2]))));
+        let mut collector = Collector::create(fetcher).unwrap();
+        let new_page = collector.new_page();
+        assert_eq!(2, collector.load_next().unwrap());
+        assert_eq!(0, collector.add_page(new_page.send()).unwrap());
+        assert_eq!(vec![1, 2, 3, 4], (0..4).filter_map(|index| collector.get_loaded(index).cloned()).collect::<Vec<_>>());
+    }
+
     #[test]
     fn it_should_return_error_from_fetcher_on_creation() {
         let fetcher = FnFetcher::<i32>(Box::new(|_from| {
@@ -115,8 +242,10 @@ mod tests {
             2 => Err(FetcherError::RequestError { inner: "fail".to_string() }),
             _ => Ok((5, vec![])),
         }));
-        let result: Vec<i32> = Collector::create(fetcher).unwrap().iter().collect();
+        let mut collector = Collector::create(fetcher).unwrap();
+        let result: Vec<i32> = collector.iter().collect();
         assert_eq!(vec![99, 98], result);
+        assert_eq!("fail", collector.failure.unwrap().to_string());
     }
 
     #[test]
@@ -187,4 +316,22 @@ mod tests {
         let result: Vec<i32> = collector.iter().skip(1).take(2).collect();
         assert_eq!(vec![98, 97], result);
     }
+
+    #[test]
+    fn it_should_load_next_page_on_demand() {
+        let fetcher = FnFetcher(Box::new(|from| match from {
+            0 => Ok((3, vec![99])),
+            1 => Ok((3, vec![98, 97])),
+            _ => panic!("should not happen"),
+        }));
+
+        let mut collector = Collector::create(fetcher).unwrap();
+        assert_eq!(None, collector.get_loaded(1));
+        assert!(collector.has_more());
+
+        assert_eq!(2, collector.load_next().unwrap());
+        assert_eq!(Some(&97), collector.get_loaded(2));
+        assert!(!collector.has_more());
+        assert_eq!(0, collector.load_next().unwrap());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use serde_json::Value;
+
+use std::convert::TryFrom;
+
+/// Date math of Elasticsearch: `now`, `now-1h`, `now/d`, `2019-04-01`, `2019-04-01||+1M/d`.
+/// The rounding floors the date, or moves it to the end of the unit for the upper bounds like `lte` does.
+pub fn parse_date_math(expression: &str, now: DateTime<Utc>, round_up: bool) -> Option<DateTime<Utc>> {
+    let expression = expression.trim();
+    let (mut date, mut math) = if let Some(math) = expression.strip_prefix("now") {
+        (now, math)
+    } else {
+        match expression.find("||") {
+            Some(position) => (parse_date(&expression[..position])?, &expression[position + 2..]),
+            // like Elasticsearch, the missing time of the date is the end of the day for the upper bounds
+            None if round_up && NaiveDate::parse_from_str(expression, "%Y-%m-%d").is_ok() => (parse_date(expression)?, "/d"),
+            None => (parse_date(expression)?, "")
+        }
+    };
+    while !math.is_empty() {
+        let operation = math.chars().next()?;
+        math = &math[operation.len_utf8()..];
+        let digits = math.chars().take_while(char::is_ascii_digit).count();
+        let amount: i64 = if digits == 0 { 1 } else { math[..digits].parse().ok()? };
+        math = &math[digits..];
+        let unit = math.chars().next()?;
+        math = &math[unit.len_utf8()..];
+        date = match operation {
+            '+' => add(date, amount, unit)?,
+            '-' => add(date, -amount, unit)?,
+            '/' if digits == 0 => round(date, unit, round_up)?,
+            _ => return None
+        };
+    }
+    Some(date)
+}
+
+/// Date in the document: the RFC 3339 date, the date with the time without the zone, or the milliseconds since the epoch
+pub fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let formats = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"];
+    if let Some(date) = formats.iter().find_map(|format| NaiveDateTime::parse_from_str(value, format).ok()) {
+        return Some(Utc.from_utc_datetime(&date));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(Utc.from_utc_datetime(&date.and_hms(0, 0, 0)));
+    }
+    None
+}
+
+/// Date of the field value, the numbers are the milliseconds since the epoch
+pub fn date_of(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(value) => parse_date(value),
+        Value::Number(millis) => millis.as_i64().and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+        _ => None
+    }
+}
+
+/// Adds the amount of the unit, `None` if the date is out of the range
+fn add(date: DateTime<Utc>, amount: i64, unit: char) -> Option<DateTime<Utc>> {
+    let seconds: i64 = match unit {
+        'y' => return add_months(date, amount.checked_mul(12)?),
+        'M' => return add_months(date, amount),
+        'w' => 7 * 24 * 3600,
+        'd' => 24 * 3600,
+        'h' | 'H' => 3600,
+        'm' => 60,
+        's' => 1,
+        _ => return None
+    };
+    let millis = amount.checked_mul(seconds)?.checked_mul(1000)?;
+    date.checked_add_signed(Duration::milliseconds(millis))
+}
+
+fn add_months(date: DateTime<Utc>, months: i64) -> Option<DateTime<Utc>> {
+    let total = (i64::from(date.year()) * 12 + i64::from(date.month0())).checked_add(months)?;
+    let year = i32::try_from(total.div_euclid(12)).ok()?;
+    let month0 = total.rem_euclid(12) as u32;
+    // the day is clamped to the last day of the month like Elasticsearch does
+    (1..=date.day()).rev()
+        .find_map(|day| date.with_day(1)?.with_year(year)?.with_month0(month0)?.with_day(day))
+}
+
+fn round(date: DateTime<Utc>, unit: char, round_up: bool) -> Option<DateTime<Utc>> {
+    let start = match unit {
+        'y' => date.with_month0(0)?.with_day0(0)?.date().and_hms(0, 0, 0),
+        'M' => date.with_day0(0)?.date().and_hms(0, 0, 0),
+        'w' => (date - Duration::days(i64::from(date.weekday().num_days_from_monday()))).date().and_hms(0, 0, 0),
+        'd' => date.date().and_hms(0, 0, 0),
+        'h' | 'H' => date.date().and_hms(date.hour(), 0, 0),
+        'm' => date.date().and_hms(date.hour(), date.minute(), 0),
+        's' => date.date().and_hms(date.hour(), date.minute(), date.second()),
+        _ => return None
+    };
+    if round_up {
+        add(start, 1, unit).map(|end| end - Duration::milliseconds(1))
+    } else {
+        Some(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.ymd(2019, 4, 10).and_hms(15, 30, 45)
+    }
+
+    fn date_math(expression: &str, round_up: bool) -> String {
+        parse_date_math(expression, now(), round_up).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn it_should_evaluate_date_math() {
+        assert_eq!("2019-04-10T14:30:45+00:00", date_math("now-1h", false));
+        assert_eq!("2019-04-10T00:00:00+00:00", date_math("now/d", false));
+        assert_eq!("2019-04-10T23:59:59.999+00:00", date_math("now/d", true));
+        assert_eq!("2019-03-18T00:00:00+00:00", date_math("now-1M+2w/w", false));
+        assert_eq!("2019-02-28T00:00:00+00:00", date_math("2019-01-31||+1M", false));
+        assert_eq!("2019-04-01T10:00:00+00:00", date_math("2019-04-01T12:00:00+02:00", false));
+        assert_eq!("2019-04-01T23:59:59.999+00:00", date_math("2019-04-01", true));
+        assert_eq!(None, parse_date_math("now-1x", now(), false));
+        assert_eq!(None, parse_date_math("yesterday", now(), false));
+        assert_eq!(None, parse_date_math("now–1h", now(), false));
+        assert_eq!(None, parse_date_math("now×1d", now(), true));
+    }
+
+    #[test]
+    fn it_should_read_dates_of_documents() {
+        assert_eq!(Some(Utc.ymd(2019, 4, 1).and_hms(10, 0, 0)), date_of(&json!("2019-04-01 10:00:00")));
+        assert_eq!(Some(Utc.ymd(2019, 4, 1).and_hms(0, 0, 0)), date_of(&json!("2019-04-01")));
+        assert_eq!(Some(Utc.ymd(2019, 4, 1).and_hms(0, 0, 1)), date_of(&json!(1_554_076_801_000_i64)));
+        assert_eq!(None, date_of(&json!("error")));
+        assert_eq!(None, date_of(&json!(9_000_000_000_000_000_i64)));
+    }
+
+    #[test]
+    fn it_should_reject_dates_out_of_range() {
+        assert_eq!(None, parse_date_math("now-9000000000000000w", now(), false));
+        assert_eq!(None, parse_date_math("now+9000000000000000000y", now(), false));
+        assert_eq!(None, parse_date_math("now+300000000M", now(), false));
+    }
+}
@@ -0,0 +1,436 @@
+mod dates;
+mod query;
+
+pub use self::dates::*;
+pub use self::query::*;
+
+use client::{Client, ClientError, Collector, Fetcher, FetcherError, PreparedRequest, SearchRequest, Sort, Total};
+use display::expand_path;
+
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use serde_json::Value;
+
+use std::cmp::{min, Ordering};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// First bytes of the gzip files
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Search of the NDJSON files on the local disk, the address of the `file` server is the path or the glob
+pub struct FileClient {
+    path: String,
+    buffer_size: usize
+}
+
+/// Serves the pages of the matched documents, the files are read again by the follow mode of the pager
+/// when the loaded documents run out
+pub struct FileFetcher {
+    search: FileSearch,
+    buffer_size: usize,
+    loaded: Mutex<LoadedFiles>
+}
+
+/// Matched documents of the files, the follow mode appends the new ones
+struct LoadedFiles {
+    documents: Vec<Value>,
+    /// Count of the matched documents of each file when it was read last time
+    counts: HashMap<PathBuf, usize>
+}
+
+struct FileSearch {
+    path: String,
+    query: Query,
+    range: Option<Query>,
+    sort: Option<Sort>
+}
+
+impl FileClient {
+    pub fn new(path: &str, buffer_size: usize) -> Self {
+        FileClient { path: path.to_string(), buffer_size }
+    }
+}
+
+impl Client for FileClient {
+    fn execute(&self, request: &SearchRequest) -> Result<Collector<Value>, ClientError> {
+        if self.path.is_empty() {
+            return Err(ClientError::RequestError { inner: "path of the files is not set, set it by --address".to_string() });
+        }
+        let now = Utc::now();
+        let query = Query::parse(&request.query, now).map_err(|err| ClientError::RequestError { inner: err.to_string() })?;
+        let range = match &request.range {
+            Some(range) => Some(
+                Query::date_range(&range.field, range.from.as_deref(), range.to.as_deref(), now)
+                    .map_err(|err| ClientError::RequestError { inner: format!("invalid time range: {}", err) })?
+            ),
+            None => None
+        };
+        let search = FileSearch { path: self.path.clone(), query, range, sort: request.sort.clone() };
+        let loaded = search.load()?;
+
+        Collector::create(FileFetcher { search, buffer_size: self.buffer_size, loaded: Mutex::new(loaded) })
+            .map_err(From::from)
+    }
+
+    fn prepare(&self, _: &SearchRequest) -> Result<Vec<PreparedRequest>, ClientError> {
+        Ok(vec![])
+    }
+}
+
+impl Fetcher<Value> for FileFetcher {
+    fn fetch_next(&self, from: usize) -> Result<(Total, Vec<Value>), FetcherError> {
+        let loaded = self.loaded.lock().unwrap_or_else(|err| err.into_inner());
+        Ok(loaded.page(from, self.buffer_size))
+    }
+
+    /// The files are read again when all loaded documents are fetched
+    fn fetch_new(&self, from: usize) -> Result<(Total, Vec<Value>), FetcherError> {
+        let mut loaded = self.loaded.lock().unwrap_or_else(|err| err.into_inner());
+        if from >= loaded.documents.len() {
+            let documents = self.search.new_documents(&mut loaded)?;
+            loaded.documents.extend(documents);
+        }
+        Ok(loaded.page(from, self.buffer_size))
+    }
+}
+
+impl LoadedFiles {
+    fn page(&self, from: usize, size: usize) -> (Total, Vec<Value>) {
+        let to = min(from + size, self.documents.len());
+        (self.documents.len().into(), self.documents.get(from..to).map(<[Value]>::to_vec).unwrap_or_default())
+    }
+}
+
+impl FileSearch {
+    /// The matched documents of all files, sorted if the sort is specified, or in the order of the files otherwise
+    fn load(&self) -> Result<LoadedFiles, FetcherError> {
+        let mut loaded = LoadedFiles { documents: vec![], counts: HashMap::new() };
+        loaded.documents = self.new_documents(&mut loaded)?;
+        Ok(loaded)
+    }
+
+    /// The matched documents after the loaded ones: the ones after the documents read before from each file,
+    /// the truncated or rotated files are read from the start. The sorted documents are taken only if they are
+    /// sorted after the last loaded document, like `search_after` of Elasticsearch.
+    fn new_documents(&self, loaded: &mut LoadedFiles) -> Result<Vec<Value>, FetcherError> {
+        let mut documents = vec![];
+        for path in self.files()? {
+            let matched: Vec<Value> = read_documents(&path)?.into_iter().filter(|document| self.matches(document)).collect();
+            let read = loaded.counts.insert(path, matched.len()).unwrap_or(0);
+            let skipped = if matched.len() < read { 0 } else { read };
+            documents.extend(matched.into_iter().skip(skipped));
+        }
+        if let Some(sort) = &self.sort {
+            if let Some(last) = loaded.documents.last().map(|document| DocumentKey::of(document, sort)) {
+                documents.retain(|document| DocumentKey::of(document, sort) > last);
+            }
+            sort_documents(&mut documents, sort);
+        }
+        Ok(documents)
+    }
+
+    fn matches(&self, document: &Value) -> bool {
+        if !self.query.matches(document) {
+            return false;
+        }
+        match &self.range {
+            Some(range) => range.matches(document),
+            None => true
+        }
+    }
+
+    fn files(&self) -> Result<Vec<PathBuf>, FetcherError> {
+        let pattern = expand_path(&self.path);
+        let paths = glob::glob(&pattern.to_string_lossy()).map_err(|err| FetcherError::RequestError {
+            inner: format!("invalid glob {}: {}", self.path, err)
+        })?;
+        let files: Vec<PathBuf> = paths
+            .filter_map(|path| path.map_err(|err| warn!("Cannot read {}: {}", err.path().display(), err.error())).ok())
+            .filter(|path| path.is_file())
+            .collect();
+        if files.is_empty() {
+            return Err(FetcherError::RequestError { inner: format!("no files match {}", self.path) });
+        }
+        debug!("Searching {} files matching {}", files.len(), self.path);
+        Ok(files)
+    }
+}
+
+/// Documents of the NDJSON file, gzip is detected by the content.
+/// The lines which are not JSON objects are skipped, `_source` of the exported hits is taken as the document.
+fn read_documents(path: &Path) -> Result<Vec<Value>, FetcherError> {
+    let read_error = |err: std::io::Error| FetcherError::RequestError { inner: format!("cannot read {}: {}", path.display(), err) };
+    let mut reader = BufReader::new(File::open(path).map_err(read_error)?);
+    let is_gzip = reader.fill_buf().map_err(read_error)?.starts_with(&GZIP_MAGIC);
+    let lines: Box<BufRead> = if is_gzip {
+        Box::new(BufReader::new(MultiGzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    };
+
+    let mut documents = vec![];
+    let mut skipped = 0;
+    for line in lines.lines() {
+        let line = line.map_err(read_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(Value::Object(mut document)) => match document.remove("_source") {
+                Some(source @ Value::Object(_)) => documents.push(source),
+                Some(source) => {
+                    document.insert("_source".to_string(), source);
+                    documents.push(Value::Object(document));
+                },
+                None => documents.push(Value::Object(document))
+            },
+            _ => skipped += 1
+        }
+    }
+    if skipped > 0 {
+        warn!("Skipped {} lines of {} which are not JSON objects", skipped, path.display());
+    }
+    Ok(documents)
+}
+
+fn sort_documents(documents: &mut [Value], sort: &Sort) {
+    documents.sort_by_cached_key(|document| DocumentKey::of(document, sort));
+}
+
+/// Position of the document in the order of the sort, like Elasticsearch: the arrays are sorted by the lowest value
+/// ascending and by the highest descending, the documents without the field are the last in both orders
+struct DocumentKey {
+    value: Option<SortKey>,
+    descending: bool
+}
+
+impl DocumentKey {
+    fn of(document: &Value, sort: &Sort) -> Self {
+        let keys = field_values(document, &sort.field).into_iter().filter_map(SortKey::of);
+        let value = if sort.descending { keys.max() } else { keys.min() };
+        DocumentKey { value, descending: sort.descending }
+    }
+}
+
+impl Ord for DocumentKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.value, &other.value) {
+            (Some(a), Some(b)) if self.descending => b.cmp(a),
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal
+        }
+    }
+}
+
+impl PartialOrd for DocumentKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for DocumentKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for DocumentKey {}
+
+/// Value of the sort field: the numbers are compared by the value, the strings which are dates by the time,
+/// other strings as the text. The numbers go before the dates and the dates before the text, so the order is total.
+#[derive(Debug)]
+enum SortKey {
+    Number(f64),
+    Date(DateTime<Utc>),
+    Text(String)
+}
+
+impl SortKey {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(number) => number.as_f64().map(SortKey::Number),
+            Value::Bool(flag) => Some(SortKey::Number(if *flag { 1.0 } else { 0.0 })),
+            Value::String(text) => Some(parse_date(text).map_or_else(|| SortKey::Text(text.clone()), SortKey::Date)),
+            _ => None
+        }
+    }
+
+    fn kind(&self) -> u8 {
+        match self {
+            SortKey::Number(_) => 0,
+            SortKey::Date(_) => 1,
+            SortKey::Text(_) => 2
+        }
+    }
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SortKey::Number(a), SortKey::Number(b)) => a.total_cmp(b),
+            (SortKey::Date(a), SortKey::Date(b)) => a.cmp(b),
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            _ => self.kind().cmp(&other.kind())
+        }
+    }
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortKey {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    fn sorted(field: &str, descending: bool, documents: Vec<Value>) -> Vec<Value> {
+        let mut documents = documents;
+        sort_documents(&mut documents, &Sort { field: field.to_string(), descending });
+        documents
+    }
+
+    #[test]
+    fn it_should_sort_documents_with_missing_fields_last() {
+        let documents = vec![json!({ "n": 10 }), json!({}), json!({ "n": 9 }), json!({ "n": [1, 20] })];
+        assert_eq!(
+            vec![json!({ "n": [1, 20] }), json!({ "n": 9 }), json!({ "n": 10 }), json!({})],
+            sorted("n", false, documents.clone())
+        );
+        assert_eq!(
+            vec![json!({ "n": [1, 20] }), json!({ "n": 10 }), json!({ "n": 9 }), json!({})],
+            sorted("n", true, documents)
+        );
+    }
+
+    #[test]
+    fn it_should_sort_dates_by_time() {
+        let documents = vec![json!({ "t": "2019-04-01T12:00:00+02:00" }), json!({ "t": "2019-04-01T11:00:00Z" })];
+        assert_eq!(
+            vec![json!({ "t": "2019-04-01T12:00:00+02:00" }), json!({ "t": "2019-04-01T11:00:00Z" })],
+            sorted("t", false, documents)
+        );
+    }
+
+    #[test]
+    fn it_should_sort_numbers_before_dates_before_text() {
+        let documents = vec![
+            json!({ "t": "2019-04-01T11:30:00 junk" }),
+            json!({ "t": "2019-04-01T11:00:00Z" }),
+            json!({ "t": 1 }),
+            json!({ "t": "2019-04-01T12:00:00+02:00" })
+        ];
+        assert_eq!(
+            vec![
+                json!({ "t": 1 }),
+                json!({ "t": "2019-04-01T12:00:00+02:00" }),
+                json!({ "t": "2019-04-01T11:00:00Z" }),
+                json!({ "t": "2019-04-01T11:30:00 junk" })
+            ],
+            sorted("t", false, documents.clone())
+        );
+        assert_eq!(
+            vec![
+                json!({ "t": "2019-04-01T11:30:00 junk" }),
+                json!({ "t": "2019-04-01T11:00:00Z" }),
+                json!({ "t": "2019-04-01T12:00:00+02:00" }),
+                json!({ "t": 1 })
+            ],
+            sorted("t", true, documents)
+        );
+    }
+
+    #[test]
+    fn it_should_search_plain_and_gzip_files() {
+        let directory = env::temp_dir().join(format!("elastic-cli-files-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("a.ndjson"),
+            "{\"level\":\"error\",\"n\":2}\nnot json\n\n{\"_index\":\"logs\",\"_source\":{\"level\":\"info\",\"n\":1}}\n"
+        ).unwrap();
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"{\"level\":\"error\",\"n\":3}\n").unwrap();
+        fs::write(directory.join("b.log.gz"), encoder.finish().unwrap()).unwrap();
+
+        let search = |query: &str, sort: Option<Sort>| FileSearch {
+            path: directory.join("*").to_string_lossy().to_string(),
+            query: Query::parse(query, Utc::now()).unwrap(),
+            range: None,
+            sort
+        }.load().map(|loaded| loaded.documents);
+        assert_eq!(
+            vec![json!({ "level": "error", "n": 2 }), json!({ "level": "info", "n": 1 }), json!({ "level": "error", "n": 3 })],
+            search("*", None).unwrap()
+        );
+        assert_eq!(
+            vec![json!({ "level": "error", "n": 3 }), json!({ "level": "error", "n": 2 })],
+            search("level:error", Some(Sort { field: "n".to_string(), descending: true })).unwrap()
+        );
+
+        let missing = FileSearch { path: directory.join("*.csv").to_string_lossy().to_string(), query: Query::All, range: None, sort: None };
+        assert_eq!(format!("no files match {}", missing.path), missing.load().err().unwrap().to_string());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn it_should_follow_documents_appended_to_files() {
+        let directory = env::temp_dir().join(format!("elastic-cli-follow-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("a.ndjson");
+        let append = |lines: &str| fs::OpenOptions::new().append(true).open(&file).unwrap().write_all(lines.as_bytes()).unwrap();
+        let client = FileClient::new(&directory.join("*").to_string_lossy(), 1);
+        let request = |sort| SearchRequest { index: "*".to_string(), query: "*".to_string(), range: None, sort };
+        let load_new = |collector: &mut Collector<Value>| {
+            let page = collector.new_page().send();
+            collector.add_page(page).unwrap()
+        };
+
+        fs::write(&file, "{\"n\":1}\n{\"n\":2}\n").unwrap();
+        let mut collector = client.execute(&request(None)).unwrap();
+        assert_eq!(1, collector.load_next().unwrap());
+        assert_eq!(0, collector.load_next().unwrap());
+        append("{\"n\":3}\n");
+        assert_eq!(1, load_new(&mut collector));
+        assert_eq!(Some(&json!({ "n": 3 })), collector.get_loaded(2));
+        assert_eq!(0, load_new(&mut collector));
+        // the rotated file is read from the start
+        fs::write(&file, "{\"n\":4}\n").unwrap();
+        assert_eq!(1, load_new(&mut collector));
+        assert_eq!(Some(&json!({ "n": 4 })), collector.get_loaded(3));
+
+        // only the documents sorted after the last loaded one are new
+        fs::write(&file, "{\"n\":2}\n{\"n\":5}\n").unwrap();
+        let mut collector = client.execute(&request(Some(Sort { field: "n".to_string(), descending: true }))).unwrap();
+        assert_eq!(1, collector.load_next().unwrap());
+        append("{\"n\":9}\n{\"n\":1}\n");
+        assert_eq!(1, load_new(&mut collector));
+        assert_eq!(
+            vec![json!({ "n": 5 }), json!({ "n": 2 }), json!({ "n": 1 })],
+            (0..3).filter_map(|index| collector.get_loaded(index).cloned()).collect::<Vec<_>>()
+        );
+        assert_eq!(0, load_new(&mut collector));
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
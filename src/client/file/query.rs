@@ -0,0 +1,609 @@
+use super::dates::{date_of, parse_date_math};
+
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Subset of the `query_string` syntax evaluated on the local documents: `field:value`, `"phrases"`, wildcards,
+/// ranges, `AND`, `OR`, `NOT`, `+`, `-`, `_exists_` and the groups. The default operator is `OR` like in Elasticsearch.
+#[derive(Debug)]
+pub enum Query {
+    All,
+    Bool { must: Vec<Query>, should: Vec<Query>, must_not: Vec<Query> },
+    /// Matches the field, or any field if the field is not specified
+    Field { field: Option<String>, matcher: Matcher }
+}
+
+#[derive(Debug)]
+pub enum Matcher {
+    Term(String),
+    Phrase(Vec<String>),
+    Wildcard(Regex),
+    Range { lower: Bound, upper: Bound },
+    Exists
+}
+
+#[derive(Debug)]
+pub enum Bound {
+    Unbounded,
+    Included(RangeValue),
+    Excluded(RangeValue)
+}
+
+/// Bound of the range, compared as the number, the date or the string depending on the value of the field
+#[derive(Debug)]
+pub struct RangeValue {
+    text: String,
+    number: Option<f64>,
+    date: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Fail, PartialEq)]
+#[fail(display = "invalid query at position {}: {}", position, message)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String
+}
+
+impl Query {
+    /// Parses the query, `now` is the base of the date math in the ranges
+    pub fn parse(query: &str, now: DateTime<Utc>) -> Result<Self, QueryError> {
+        let mut parser = Parser { tokens: tokenize(query)?, position: 0, end: query.chars().count(), now };
+        let query = parser.parse_clauses(None)?;
+        match parser.tokens.get(parser.position) {
+            Some((position, _)) => Err(QueryError { position: *position, message: "unexpected ')'".to_string() }),
+            None => Ok(query)
+        }
+    }
+
+    /// Query of the range of the date field like the `range` filter with `gte` and `lte`
+    pub fn date_range(field: &str, from: Option<&str>, to: Option<&str>, now: DateTime<Utc>) -> Result<Self, String> {
+        let bound = |bound: Option<&str>, round_up: bool| match bound {
+            Some(expression) => parse_date_math(expression, now, round_up)
+                .map(|date| Bound::Included(RangeValue { text: expression.to_string(), number: None, date: Some(date) }))
+                .ok_or_else(|| format!("invalid date '{}'", expression)),
+            None => Ok(Bound::Unbounded)
+        };
+        let matcher = Matcher::Range { lower: bound(from, false)?, upper: bound(to, true)? };
+        Ok(Query::Field { field: Some(field.to_string()), matcher })
+    }
+
+    pub fn matches(&self, document: &Value) -> bool {
+        match self {
+            Query::All => true,
+            Query::Bool { must, should, must_not } =>
+                must.iter().all(|query| query.matches(document))
+                    && !must_not.iter().any(|query| query.matches(document))
+                    && (!must.is_empty() || should.is_empty() || should.iter().any(|query| query.matches(document))),
+            Query::Field { field: Some(field), matcher } => field_values(document, field).iter().any(|value| matcher.matches(value)),
+            Query::Field { field: None, matcher } => leaf_values(document).iter().any(|value| matcher.matches(value))
+        }
+    }
+}
+
+impl Matcher {
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (_, Value::Null) | (_, Value::Array(_)) | (_, Value::Object(_)) => false,
+            (Matcher::Exists, _) => true,
+            (Matcher::Term(term), Value::String(text)) =>
+                text.eq_ignore_ascii_case(term) || contains_phrase(&tokens(text), &tokens(term)),
+            (Matcher::Term(term), Value::Number(number)) =>
+                term.parse::<f64>().ok().is_some_and(|term| number.as_f64() == Some(term)),
+            (Matcher::Term(term), Value::Bool(flag)) => term.eq_ignore_ascii_case(&flag.to_string()),
+            (Matcher::Phrase(phrase), Value::String(text)) => contains_phrase(&tokens(text), phrase),
+            (Matcher::Phrase(phrase), _) => *phrase == tokens(&scalar_text(value)),
+            (Matcher::Wildcard(pattern), _) => {
+                let text = scalar_text(value);
+                pattern.is_match(&text) || tokens(&text).iter().any(|token| pattern.is_match(token))
+            },
+            (Matcher::Range { lower, upper }, _) => {
+                let above = match lower {
+                    Bound::Unbounded => true,
+                    Bound::Included(bound) => bound.compare(value).is_some_and(|order| order != Ordering::Greater),
+                    Bound::Excluded(bound) => bound.compare(value) == Some(Ordering::Less)
+                };
+                let below = match upper {
+                    Bound::Unbounded => true,
+                    Bound::Included(bound) => bound.compare(value).is_some_and(|order| order != Ordering::Less),
+                    Bound::Excluded(bound) => bound.compare(value) == Some(Ordering::Greater)
+                };
+                above && below
+            }
+        }
+    }
+}
+
+impl RangeValue {
+    fn new(text: &str, now: DateTime<Utc>, round_up: bool) -> Self {
+        RangeValue { text: text.to_string(), number: text.parse().ok(), date: parse_date_math(text, now, round_up) }
+    }
+
+    /// Order of the bound relative to the value, `None` if they are not comparable
+    fn compare(&self, value: &Value) -> Option<Ordering> {
+        let number = match value {
+            Value::Number(number) => number.as_f64(),
+            Value::String(text) => text.parse().ok(),
+            _ => None
+        };
+        if let (Some(bound), Some(number)) = (self.number, number) {
+            return bound.partial_cmp(&number);
+        }
+        if let (Some(bound), Some(date)) = (self.date, date_of(value)) {
+            return Some(bound.cmp(&date));
+        }
+        match value {
+            Value::String(text) => Some(self.text.as_str().cmp(text.as_str())),
+            _ => None
+        }
+    }
+}
+
+/// Values of the field, the path follows the nested objects and the arrays, `a.b` is either the key or the path
+pub fn field_values<'a>(document: &'a Value, field: &str) -> Vec<&'a Value> {
+    let mut values = vec![];
+    collect_field(document, field, &mut values);
+    values
+}
+
+fn collect_field<'a>(value: &'a Value, path: &str, values: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| collect_field(item, path, values)),
+        _ if path.is_empty() => values.push(value),
+        Value::Object(object) => {
+            if let Some(value) = object.get(path) {
+                collect_field(value, "", values);
+            }
+            for (position, _) in path.match_indices('.') {
+                if let Some(value) = object.get(&path[..position]) {
+                    collect_field(value, &path[position + 1..], values);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+fn leaf_values(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().flat_map(leaf_values).collect(),
+        Value::Object(object) => object.values().flat_map(leaf_values).collect(),
+        _ => vec![value]
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        _ => value.to_string()
+    }
+}
+
+/// Lowercase words of the text, roughly like the standard analyzer
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn contains_phrase(tokens: &[String], phrase: &[String]) -> bool {
+    !phrase.is_empty() && tokens.windows(phrase.len()).any(|window| window == phrase)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+    Plus,
+    Minus,
+    /// The field name followed by the colon
+    Field(String),
+    /// The word, the wildcards are kept only if they are not escaped
+    Word { text: String, wildcard: bool },
+    Phrase(String),
+    Range { lower: String, lower_included: bool, upper: String, upper_included: bool }
+}
+
+fn tokenize(query: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+    let mut position = 0;
+    while let Some(&c) = chars.peek() {
+        let start = position;
+        let error = |message: &str| QueryError { position: start, message: message.to_string() };
+        // the minus is the part of the value after the field, e.g. `delta:-5`, the colons are kept in the value too,
+        // e.g. `time:>2019-04-01T10:00:00`
+        let after_field = matches!(tokens.last(), Some((_, Token::Field(_))));
+        let token = match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+                position += 1;
+                continue;
+            },
+            '(' | ')' | '+' | '-' | '!' if c != '-' || !after_field => {
+                chars.next();
+                position += 1;
+                match c {
+                    '(' => Token::LeftParen,
+                    ')' => Token::RightParen,
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    _ => Token::Not
+                }
+            },
+            '"' => {
+                chars.next();
+                position += 1;
+                let (text, _) = read_until(&mut chars, &mut position, |c| c == '"');
+                if chars.next().is_none() {
+                    return Err(error("unterminated phrase"));
+                }
+                position += 1;
+                Token::Phrase(text)
+            },
+            '[' | '{' => {
+                chars.next();
+                position += 1;
+                let (content, _) = read_until(&mut chars, &mut position, |c| c == ']' || c == '}');
+                let upper_included = match chars.next() {
+                    Some(end) => end == ']',
+                    None => return Err(error("unterminated range"))
+                };
+                position += 1;
+                let bounds: Vec<&str> = content.split(" TO ").map(str::trim).collect();
+                match bounds.as_slice() {
+                    [lower, upper] => Token::Range {
+                        lower: lower.to_string(),
+                        lower_included: c == '[',
+                        upper: upper.to_string(),
+                        upper_included
+                    },
+                    _ => return Err(error("range must be [lower TO upper]"))
+                }
+            },
+            _ => {
+                let (text, wildcard) = read_until(&mut chars, &mut position, |c| {
+                    c.is_whitespace() || c == '(' || c == ')' || (c == ':' && !after_field)
+                });
+                if chars.peek() == Some(&':') {
+                    chars.next();
+                    position += 1;
+                    Token::Field(text)
+                } else {
+                    match text.as_str() {
+                        "AND" | "&&" => Token::And,
+                        "OR" | "||" => Token::Or,
+                        "NOT" => Token::Not,
+                        _ => Token::Word { text, wildcard }
+                    }
+                }
+            }
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+/// Reads the text until the character, the escaped characters are taken as they are.
+/// Returns the text and whether it has unescaped wildcards.
+fn read_until(chars: &mut Peekable<Chars>, position: &mut usize, end: impl Fn(char) -> bool) -> (String, bool) {
+    let mut text = String::new();
+    let mut wildcard = false;
+    while let Some(&c) = chars.peek() {
+        if end(c) {
+            break;
+        }
+        chars.next();
+        *position += 1;
+        match c {
+            '\\' => if let Some(escaped) = chars.next() {
+                *position += 1;
+                // the escaped wildcards are matched literally
+                if escaped == '*' || escaped == '?' {
+                    text.push('\\');
+                }
+                text.push(escaped);
+            },
+            '*' | '?' => {
+                wildcard = true;
+                text.push(c);
+            },
+            _ => text.push(c)
+        }
+    }
+    (text, wildcard)
+}
+
+#[derive(PartialEq)]
+enum Occur {
+    Must,
+    Should,
+    MustNot
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// Position of the end of the query
+    end: usize,
+    now: DateTime<Utc>
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn error(&self, message: &str) -> QueryError {
+        let position = self.tokens.get(self.position).map_or(self.end, |(position, _)| *position);
+        QueryError { position, message: message.to_string() }
+    }
+
+    /// Clauses until the closing parenthesis, combined like the classic query parser of Lucene does:
+    /// `AND` makes both clauses required, `NOT` and `-` prohibit the clause, `+` requires it, others are optional
+    fn parse_clauses(&mut self, field: Option<&str>) -> Result<Query, QueryError> {
+        let mut clauses: Vec<(Occur, Query)> = vec![];
+        while self.peek().is_some() && self.peek() != Some(&Token::RightParen) {
+            let conjunction = match self.peek() {
+                Some(Token::And) | Some(Token::Or) if clauses.is_empty() => return Err(self.error("query cannot start with the operator")),
+                Some(Token::And) | Some(Token::Or) => self.next(),
+                _ => None
+            };
+            if conjunction == Some(Token::And) {
+                if let Some((occur, _)) = clauses.last_mut() {
+                    if *occur == Occur::Should {
+                        *occur = Occur::Must;
+                    }
+                }
+            }
+            let occur = match self.peek() {
+                Some(Token::Not) | Some(Token::Minus) => {
+                    self.next();
+                    Occur::MustNot
+                },
+                Some(Token::Plus) => {
+                    self.next();
+                    Occur::Must
+                },
+                _ if conjunction == Some(Token::And) => Occur::Must,
+                _ => Occur::Should
+            };
+            let clause = self.parse_clause(field)?;
+            clauses.push((occur, clause));
+        }
+
+        if clauses.len() == 1 && clauses[0].0 != Occur::MustNot {
+            return Ok(clauses.pop().expect("one clause").1);
+        }
+        let (mut must, mut should, mut must_not) = (vec![], vec![], vec![]);
+        for (occur, clause) in clauses {
+            match occur {
+                Occur::Must => must.push(clause),
+                Occur::Should => should.push(clause),
+                Occur::MustNot => must_not.push(clause)
+            }
+        }
+        if must.is_empty() && should.is_empty() && must_not.is_empty() {
+            return Ok(Query::All);
+        }
+        Ok(Query::Bool { must, should, must_not })
+    }
+
+    fn parse_clause(&mut self, field: Option<&str>) -> Result<Query, QueryError> {
+        match self.next() {
+            Some(Token::LeftParen) => {
+                let query = self.parse_clauses(field)?;
+                match self.next() {
+                    Some(Token::RightParen) => Ok(query),
+                    _ => {
+                        self.position -= 1;
+                        Err(self.error("missing ')'"))
+                    }
+                }
+            },
+            Some(Token::Field(_)) if field.is_some() => {
+                self.position -= 1;
+                Err(self.error("field cannot be nested in the group of the field"))
+            },
+            Some(Token::Field(name)) => match self.peek() {
+                // `*:value` searches all fields like the bare value
+                Some(Token::Word { .. }) | Some(Token::Phrase(_)) | Some(Token::Range { .. }) | Some(Token::LeftParen) if name == "*" =>
+                    self.parse_clause(None),
+                Some(Token::Word { .. }) | Some(Token::Phrase(_)) | Some(Token::Range { .. }) | Some(Token::LeftParen) =>
+                    self.parse_clause(Some(&name)),
+                _ => Err(self.error(&format!("value of the field '{}' is missing", name)))
+            },
+            Some(Token::Word { text, wildcard }) => Ok(self.word(field, &text, wildcard)),
+            Some(Token::Phrase(text)) => Ok(Query::Field { field: field.map(ToString::to_string), matcher: Matcher::Phrase(tokens(&text)) }),
+            Some(Token::Range { lower, lower_included, upper, upper_included }) => {
+                let lower = self.bound(&lower, lower_included, false);
+                let upper = self.bound(&upper, upper_included, true);
+                Ok(Query::Field { field: field.map(ToString::to_string), matcher: Matcher::Range { lower, upper } })
+            },
+            _ => {
+                self.position -= 1;
+                Err(self.error("expected a term"))
+            }
+        }
+    }
+
+    fn word(&self, field: Option<&str>, text: &str, wildcard: bool) -> Query {
+        if field == Some("_exists_") {
+            return Query::Field { field: Some(text.to_string()), matcher: Matcher::Exists };
+        }
+        if wildcard && text == "*" {
+            return match field {
+                Some(field) => Query::Field { field: Some(field.to_string()), matcher: Matcher::Exists },
+                None => Query::All
+            };
+        }
+        let matcher = if let Some(value) = text.strip_prefix(">=") {
+            Matcher::Range { lower: self.bound(value, true, false), upper: Bound::Unbounded }
+        } else if let Some(value) = text.strip_prefix("<=") {
+            Matcher::Range { lower: Bound::Unbounded, upper: self.bound(value, true, true) }
+        } else if let Some(value) = text.strip_prefix('>') {
+            Matcher::Range { lower: self.bound(value, false, false), upper: Bound::Unbounded }
+        } else if let Some(value) = text.strip_prefix('<') {
+            Matcher::Range { lower: Bound::Unbounded, upper: self.bound(value, false, true) }
+        } else if wildcard {
+            Matcher::Wildcard(wildcard_of(text))
+        } else {
+            Matcher::Term(text.replace("\\*", "*").replace("\\?", "?"))
+        };
+        Query::Field { field: field.map(ToString::to_string), matcher }
+    }
+
+    fn bound(&self, value: &str, included: bool, upper: bool) -> Bound {
+        if value == "*" {
+            return Bound::Unbounded;
+        }
+        // like Elasticsearch, `lte` and `gt` round the date up, `gte` and `lt` round it down
+        let value = RangeValue::new(value, self.now, upper == included);
+        if included { Bound::Included(value) } else { Bound::Excluded(value) }
+    }
+}
+
+/// Wildcard pattern of the word, the escaped wildcards are matched literally
+fn wildcard_of(text: &str) -> Regex {
+    let mut pattern = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => pattern.push_str(&regex::escape(&chars.next().map(|c| c.to_string()).unwrap_or_default())),
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string()))
+        }
+    }
+    RegexBuilder::new(&format!("^{}$", pattern))
+        .case_insensitive(true)
+        .build()
+        .expect("escaped wildcard is valid regex")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    fn document() -> Value {
+        json!({
+            "@timestamp": "2019-04-10T12:00:00Z",
+            "level": "Error",
+            "message": "Request to /api/users timed out after 30s",
+            "status": 504,
+            "duration": 30.5,
+            "tags": ["api", "timeout"],
+            "user": { "name": "bob", "roles": [{ "name": "admin" }] },
+            "host.name": "web-1",
+            "cached": false
+        })
+    }
+
+    fn matches(query: &str) -> bool {
+        Query::parse(query, Utc.ymd(2019, 4, 10).and_hms(15, 30, 0)).unwrap().matches(&document())
+    }
+
+    #[test]
+    fn it_should_match_terms_and_phrases() {
+        assert!(matches("level:error"));
+        assert!(matches("level: Error"));
+        assert!(matches("message:timed"));
+        assert!(matches("message:\"timed out\""));
+        assert!(!matches("message:\"out timed\""));
+        assert!(matches("status:504"));
+        assert!(matches("cached:false"));
+        assert!(matches("tags:timeout"));
+        assert!(matches("timeout"));
+        assert!(!matches("warning"));
+    }
+
+    #[test]
+    fn it_should_follow_nested_and_dotted_fields() {
+        assert!(matches("user.name:bob"));
+        assert!(matches("user.roles.name:admin"));
+        assert!(matches("host.name:\"web-1\""));
+        assert!(matches("_exists_:user.name"));
+        assert!(matches("user.name:*"));
+        assert!(!matches("_exists_:user.email"));
+    }
+
+    #[test]
+    fn it_should_match_wildcards() {
+        assert!(matches("user.name:b*"));
+        assert!(matches("user.name:b?b"));
+        assert!(matches("message:tim*"));
+        assert!(matches("host.name:web-*"));
+        assert!(!matches("user.name:a*"));
+        assert!(matches("*"));
+        assert!(matches("*:*"));
+    }
+
+    #[test]
+    fn it_should_match_ranges() {
+        assert!(matches("status:[500 TO 599]"));
+        assert!(matches("status:[500 TO *]"));
+        assert!(!matches("status:{500 TO 504}"));
+        assert!(matches("status:{500 TO 504]"));
+        assert!(matches("duration:>30"));
+        assert!(!matches("duration:<=30"));
+        assert!(matches("status:>=504"));
+        assert!(matches("@timestamp:[now-1d TO now]"));
+        assert!(matches("@timestamp:>=2019-04-10"));
+        assert!(!matches("@timestamp:<2019-04-10T12:00:00Z"));
+        assert!(matches("@timestamp:[2019-04-10 TO 2019-04-10]"));
+        assert!(matches("user.name:[a TO c]"));
+        assert!(!matches("@timestamp:>now×1d"));
+    }
+
+    #[test]
+    fn it_should_combine_clauses_like_query_string() {
+        assert!(matches("level:error AND status:504"));
+        assert!(!matches("level:error AND status:200"));
+        assert!(matches("level:warning OR status:504"));
+        assert!(matches("level:warning status:504"));
+        assert!(!matches("level:error AND NOT status:504"));
+        assert!(!matches("level:error -status:504"));
+        assert!(!matches("+level:warning status:504"));
+        assert!(matches("NOT level:warning"));
+        assert!(matches("(level:warning OR level:error) AND tags:api"));
+        assert!(matches("level:(warning OR error)"));
+        assert!(!matches("level:(warning OR info)"));
+        assert!(matches("level:error && !user.name:alice"));
+    }
+
+    #[test]
+    fn it_should_filter_date_range() {
+        let now = Utc.ymd(2019, 4, 10).and_hms(15, 30, 0);
+        assert!(Query::date_range("@timestamp", Some("now-4h"), None, now).unwrap().matches(&document()));
+        assert!(!Query::date_range("@timestamp", Some("now-1h"), Some("now"), now).unwrap().matches(&document()));
+        assert!(Query::date_range("@timestamp", None, Some("2019-04-10||/d"), now).unwrap().matches(&document()));
+        assert!(Query::date_range("@timestamp", Some("yesterday"), None, now).is_err());
+    }
+
+    #[test]
+    fn it_should_report_invalid_queries() {
+        let parse = |query: &str| Query::parse(query, Utc::now()).unwrap_err();
+        assert_eq!(QueryError { position: 6, message: "value of the field 'level' is missing".to_string() }, parse("level:"));
+        assert_eq!("invalid query at position 6: unterminated phrase", parse("level:\"error").to_string());
+        assert_eq!("missing ')'", parse("(level:error").message);
+        assert_eq!("unexpected ')'", parse("level:error)").message);
+        assert_eq!("query cannot start with the operator", parse("AND level:error").message);
+        assert_eq!("range must be [lower TO upper]", parse("status:[1 2]").message);
+    }
+}
@@ -0,0 +1,99 @@
+use super::{ClientError, REDACTED, configure_tls, resolve_proxy};
+
+use config::{ElasticSearchServer, HeaderValueSource, SecretsReader};
+use reqwest::{Proxy, Url};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+use std::env;
+
+/// HTTP client with the connection settings of the server
+pub fn http_client(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<reqwest::Client, ClientError> {
+    let client_cert_password = match &server.tls.client_cert {
+        Some(_) => secrets.read(&server.client_cert_password_key())
+            .map_err(|err| {
+                error!("Cannot read the password of the client certificate: {}", err);
+                ClientError::RequestError { inner: format!("cannot read the password of the client certificate: {}", err) }
+            })?
+            .unwrap_or_default(),
+        None => String::new()
+    };
+    if server.tls.insecure_skip_verify {
+        warn!("TLS certificate verification is DISABLED for the server {}, the connection is NOT secure", server.name);
+    }
+
+    let mut builder = configure_tls(reqwest::Client::builder(), &server.tls, &client_cert_password)
+        .map_err(|err| {
+            error!("Invalid TLS settings of the server {}: {}", server.name, err);
+            ClientError::RequestError { inner: format!("invalid TLS settings: {}", err) }
+        })?
+        .default_headers(headers(server, secrets)?)
+        .connect_timeout(server.request.connect_timeout())
+        .timeout(server.request.read_timeout());
+
+    // reqwest doesn't read the proxy environment variables, they are resolved with the settings of the server
+    if let Some(proxy) = resolve_proxy(server, |name| env::var(name).ok())? {
+        builder = builder.proxy(proxy_of(&proxy)?);
+    }
+    builder.build()
+        .map_err(|err| {
+            error!("Cannot create HTTP client: {}", err);
+            ClientError::RequestError { inner: format!("cannot create HTTP client: {}", err) }
+        })
+}
+
+fn proxy_of(address: &str) -> Result<Proxy, ClientError> {
+    let invalid_proxy = |err: String| {
+        error!("Invalid proxy {}: {}", address, err);
+        ClientError::RequestError { inner: format!("invalid proxy {}: {}", address, err) }
+    };
+    let mut url = Url::parse(address).map_err(|err| invalid_proxy(err.to_string()))?;
+    let username = url.username().to_string();
+    let password = url.password().map(ToString::to_string);
+    // credentials of the proxy are sent as the Proxy-Authorization header
+    url.set_username("").and_then(|_| url.set_password(None))
+        .map_err(|_| invalid_proxy("cannot remove the credentials".to_string()))?;
+
+    let proxy = Proxy::all(url).map_err(|err| invalid_proxy(err.to_string()))?;
+    Ok(if username.is_empty() {
+        proxy
+    } else {
+        proxy.basic_auth(&username, &password.unwrap_or_default())
+    })
+}
+
+/// Custom headers of the server printed by `--dry-run`, the values from the keyring are redacted
+pub fn redacted_headers(server: &ElasticSearchServer) -> Vec<(String, String)> {
+    server.headers.iter()
+        .map(|(name, source)| match source {
+            HeaderValueSource::Plain(value) => (name.clone(), value.clone()),
+            HeaderValueSource::Keyring { .. } => (name.clone(), REDACTED.to_string())
+        })
+        .collect()
+}
+
+/// Custom headers of the server, the secret values are read from the keyring
+fn headers(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<HeaderMap, ClientError> {
+    let invalid_header = |name: &str, err: String| {
+        error!("Invalid header {}: {}", name, err);
+        ClientError::RequestError { inner: format!("invalid header {}: {}", name, err) }
+    };
+    let mut headers = HeaderMap::new();
+    for (name, source) in &server.headers {
+        let value = match source {
+            HeaderValueSource::Plain(value) => value.clone(),
+            HeaderValueSource::Keyring { keyring } => secrets.read(keyring)
+                .map_err(|err| {
+                    error!("Cannot read the value of the header {}: {}", name, err);
+                    ClientError::RequestError { inner: format!("cannot read the value of the header {}: {}", name, err) }
+                })?
+                .ok_or_else(|| ClientError::RequestError {
+                    inner: format!("value of the header {} is not found in the keyring, use config update server {} --secret-header {}",
+                                   name, server.name, name)
+                })?
+        };
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| invalid_header(name, err.to_string()))?;
+        let header_value = HeaderValue::from_str(&value).map_err(|err| invalid_header(name, err.to_string()))?;
+        headers.insert(header_name, header_value);
+    }
+    Ok(headers)
+}
@@ -1,129 +1,119 @@
-use super::{Client, SearchRequest, ClientError, Fetcher, FetcherError, Collector};
+use super::{ApiRequest, ClientError, Connection, FetcherError, PreparedRequest, Transport};
 
-use config::{ElasticSearchServer, SecretsReader, Credentials};
+use config::{ElasticSearchServer, ElasticSearchServerType, SecretsReader};
+use reqwest::{Method, Url};
 use serde_json::Value;
-use elastic::prelude::SearchResponse;
-use reqwest::Url;
-use std::sync::Arc;
 
-pub struct KibanaProxyClient {
-    secrets: Arc<SecretsReader>,
-    server_config: ElasticSearchServer,
-    buffer_size: usize
+/// Header of the security plugin selecting the tenant of the user
+const TENANT_HEADER: &str = "securitytenant";
+
+/// Requests sent to Elasticsearch or OpenSearch through the console proxy of Kibana or OpenSearch Dashboards
+pub struct KibanaProxyTransport {
+    connection: Connection,
+    address: Url,
+    console: ConsoleProxy
 }
 
-pub struct KibanaProxyFetcher {
-    url: Url,
-    credentials: Option<Credentials>,
-    client: reqwest::Client,
-    query: String,
-    buffer_size: usize
+/// Console proxy API of Kibana or OpenSearch Dashboards
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsoleProxy {
+    pub product: &'static str,
+    pub path: &'static str,
+    /// Header required by the XSRF protection
+    pub xsrf_header: &'static str,
+    pub tenant: Option<String>
 }
 
-impl KibanaProxyClient {
-    pub fn create(
-        secrets: Arc<SecretsReader>,
-        server_config: ElasticSearchServer,
-        buffer_size: usize
-    ) -> Self {
-        KibanaProxyClient {
-            secrets,
-            server_config,
-            buffer_size
+impl ConsoleProxy {
+    pub fn of(server: &ElasticSearchServer) -> Self {
+        let xsrf_header = match server.server_type {
+            ElasticSearchServerType::OpenSearchDashboards => "osd-xsrf",
+            _ => "kbn-xsrf"
+        };
+        ConsoleProxy {
+            product: server.server_type.product_name(),
+            path: "/api/console/proxy",
+            xsrf_header,
+            tenant: server.tenant.clone()
         }
     }
-}
 
-impl Client for KibanaProxyClient {
-    fn execute(&self, request: &SearchRequest) -> Result<Collector<Value>, ClientError> {
-        let client = reqwest::Client::new();
+    /// URL of the proxy, the method and the path of the API request are passed as the query parameters
+    pub fn url(&self, address: &Url, request: &ApiRequest) -> Url {
+        let mut url = address.clone();
+        url.set_path(self.path);
+        url.query_pairs_mut()
+            .clear()
+            .append_pair("method", request.method.as_str())
+            .append_pair("path", &request.encoded_path());
+        url
+    }
 
-        let mut url = Url::parse_with_params(
-            self.server_config.server.clone().as_ref(),
-            vec![("method", "POST"), ("path", format!("{}/_search", request.index).as_ref())]
-        ).map_err(|err| {
+    /// Headers required by Kibana and the security plugin
+    pub fn headers(&self) -> Vec<(&str, &str)> {
+        let mut headers = vec![(self.xsrf_header, "reporting")];
+        if let Some(tenant) = &self.tenant {
+            headers.push((TENANT_HEADER, tenant.as_str()));
+        }
+        headers
+    }
+}
+
+impl KibanaProxyTransport {
+    pub fn create(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<Self, ClientError> {
+        let address = Url::parse(&server.address()?).map_err(|err| {
             error!("Invalid server address: {}", err);
             ClientError::RequestError { inner: format!("invalid server address: {}", err) }
         })?;
-        url.set_path("/api/console/proxy");
-
-        let credentials = self.server_config.username.as_ref()
-            .map(|username| {
-                self.secrets.get_credentials(&username).map_err(|err| {
-                    error!("Cannot read credentials: {}", err);
-                    ClientError::RequestError { inner: format!("cannot read credentials: {}", err) }
-                })
-            })
-            .unwrap_or_else(|| Ok(None))?;
-
+        Ok(KibanaProxyTransport {
+            connection: Connection::create(server, secrets)?,
+            address,
+            console: ConsoleProxy::of(server)
+        })
+    }
+}
 
-        let fetcher = KibanaProxyFetcher::create(url, credentials, client, request, self.buffer_size);
+impl Transport for KibanaProxyTransport {
+    fn product(&self) -> &'static str {
+        self.connection.product()
+    }
 
-        Collector::create(fetcher)
-            .map_err(From::from)
+    fn send(&self, request: &ApiRequest) -> Result<Value, FetcherError> {
+        let url = self.console.url(&self.address, request);
+        // the proxy accepts only POST, the method of the API request is the parameter
+        self.connection.send(request, Method::POST, &url, &self.console.headers())
     }
-}
 
-impl KibanaProxyFetcher {
-    pub fn create(
-        url: Url,
-        credentials: Option<Credentials>,
-        client: reqwest::Client,
-        request: &SearchRequest,
-        buffer_size: usize
-    ) -> KibanaProxyFetcher {
-        KibanaProxyFetcher {
-            url,
-            credentials,
-            client,
-            query: request.query.clone(),
-            buffer_size
-        }
+    fn prepare(&self, request: &ApiRequest) -> PreparedRequest {
+        let url = self.console.url(&self.address, request);
+        self.connection.prepare(request, Method::POST, &url, &self.console.headers())
     }
 }
 
-impl Fetcher<Value> for KibanaProxyFetcher {
-    fn fetch_next(&self, from: usize) -> Result<(usize, Vec<Value>), FetcherError> {
-        let mut request = self.client.post(self.url.clone());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if let Some(Credentials { username, password }) = &self.credentials {
-            request = request.basic_auth(username.to_owned(), Some(password.to_owned()));
-        }
+    fn console(tenant: Option<&str>) -> ConsoleProxy {
+        ConsoleProxy { product: "kibana", path: "/api/console/proxy", xsrf_header: "kbn-xsrf", tenant: tenant.map(ToString::to_string) }
+    }
 
-        request
-            .header("kbn-xsrf", "reporting")
-            .body(json!({
-                "size": self.buffer_size,
-                "from": from,
-                "query": {
-                    "query_string" : {
-                        "query" : self.query
-                    }
-                }
-            }).to_string())
-            .send()
-            .map_err(|err| {
-                error!("Cannot read response from kibana: {}", err);
-                FetcherError::RequestError { inner: format!("cannot read response from kibana: {}", err) }
-            })
-            .and_then(|resp| {
-                if resp.status().is_success() {
-                    Ok(resp)
-                } else {
-                    error!("Kibana responded {}", resp.status());
-                    Err(FetcherError::RequestError { inner: format!("kibana responded {}", resp.status()) })
-                }
-            })
-            .and_then(|mut resp| {
-                resp.json::<SearchResponse<Value>>()
-                    .map_err(|err| {
-                        error!("Cannot parse json response from kibana: {}", err);
-                        FetcherError::RequestError { inner: format!("cannot parse json response from kibana: {}", err) }
-                    })
-            })
-            .map(|resp| {
-                (resp.total() as usize, resp.documents().cloned().collect())
-            })
+    #[test]
+    fn it_should_pass_method_and_path_to_proxy() {
+        let address = Url::parse("https://kibana.example.com").unwrap();
+        assert_eq!(
+            "https://kibana.example.com/api/console/proxy?method=POST&path=%2Flogs-*%2F_search",
+            console(None).url(&address, &ApiRequest::post(&["logs-*", "_search"], json!({}))).to_string()
+        );
+        assert_eq!(
+            "https://kibana.example.com/api/console/proxy?method=GET&path=%2F",
+            console(None).url(&address, &ApiRequest::get(&[])).to_string()
+        );
+    }
 
+    #[test]
+    fn it_should_send_tenant_header() {
+        assert_eq!(vec![("kbn-xsrf", "reporting")], console(None).headers());
+        assert_eq!(vec![("kbn-xsrf", "reporting"), ("securitytenant", "global")], console(Some("global")).headers());
     }
 }
@@ -1,17 +1,172 @@
 mod fetcher;
-pub mod elastic;
+mod auth;
+mod aws;
+mod sigv4;
+mod tls;
+mod proxy;
+mod retry;
+mod response;
+mod version;
+mod http;
+mod transport;
+mod dry_run;
+mod record;
+pub mod direct;
+pub mod file;
 pub mod kibana;
+pub mod search;
 pub mod stub;
 
 pub use self::fetcher::*;
+pub use self::auth::*;
+pub use self::aws::*;
+pub use self::sigv4::*;
+pub use self::tls::*;
+pub use self::proxy::*;
+pub use self::retry::*;
+pub use self::response::*;
+pub use self::version::*;
+pub use self::http::*;
+pub use self::transport::*;
+pub use self::dry_run::*;
+pub use self::record::*;
 
+use config::CloudIdError;
+use serde_json::{Value, Map};
+
+pub const DEFAULT_TIME_FIELD: &str = "@timestamp";
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct SearchRequest {
     pub index: String,
-    pub query: String
+    pub query: String,
+    pub range: Option<TimeRange>,
+    pub sort: Option<Sort>
+}
+
+/// Limits the search by the date field, bounds are dates or date math expressions, e.g. `now-1h`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeRange {
+    pub field: String,
+    pub from: Option<String>,
+    pub to: Option<String>
+}
+
+/// Sorting by the field, the results are sorted by the score if not specified
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sort {
+    pub field: String,
+    pub descending: bool
+}
+
+impl Sort {
+    /// Parses `field`, `field:asc` or `field:desc`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (field, order) = match value.rfind(':') {
+            Some(position) => (&value[..position], &value[position + 1..]),
+            None => (value, "asc")
+        };
+        if field.is_empty() {
+            return Err("sort field must be specified".to_string());
+        }
+        match order {
+            "asc" => Ok(Sort { field: field.to_string(), descending: false }),
+            "desc" => Ok(Sort { field: field.to_string(), descending: true }),
+            _ => Err(format!("unknown sort order '{}', expected asc or desc", order))
+        }
+    }
+}
+
+impl SearchRequest {
+    /// Body of the search request for the page of the results
+    pub fn body(&self, from: usize, size: usize) -> Value {
+        let mut body = json!({
+            "size": size,
+            "from": from,
+            "query": self.query_body()
+        });
+        if let Some(sort) = &self.sort {
+            let mut field = Map::new();
+            field.insert(sort.field.clone(), json!({ "order": if sort.descending { "desc" } else { "asc" } }));
+            body["sort"] = json!([field]);
+        }
+        body
+    }
+
+    /// The `query` part of the search request body
+    pub fn query_body(&self) -> Value {
+        let query_string = json!({
+            "query_string" : {
+                "query" : self.query
+            }
+        });
+        match &self.range {
+            Some(range) => {
+                let mut bounds = Map::new();
+                if let Some(from) = &range.from {
+                    bounds.insert("gte".to_string(), Value::String(from.clone()));
+                }
+                if let Some(to) = &range.to {
+                    bounds.insert("lte".to_string(), Value::String(to.clone()));
+                }
+                let mut field = Map::new();
+                field.insert(range.field.clone(), Value::Object(bounds));
+                json!({
+                    "bool": {
+                        "must": query_string,
+                        "filter": { "range": field }
+                    }
+                })
+            },
+            None => query_string
+        }
+    }
+}
+
+/// Pages of the search request, adapted to the version of the server
+#[derive(Clone, Debug, PartialEq)]
+pub struct PagedSearch {
+    pub request: SearchRequest,
+    pub page_size: usize,
+    /// Counts all hits instead of the first 10,000, unknown to the servers before Elasticsearch 7
+    pub track_total_hits: bool
+}
+
+impl PagedSearch {
+    pub fn new(request: &SearchRequest, page_size: usize, version: Option<&ServerVersion>) -> Self {
+        PagedSearch {
+            request: request.clone(),
+            page_size,
+            track_total_hits: version.is_some_and(ServerVersion::supports_track_total_hits)
+        }
+    }
+
+    /// Body of the request for the page starting from the document
+    pub fn body(&self, from: usize) -> Value {
+        let mut body = self.request.body(from, self.page_size);
+        if self.track_total_hits {
+            body["track_total_hits"] = Value::Bool(true);
+        }
+        body
+    }
+
+    /// Body of the request for the page after the hit with the sort values, `search_after` is not limited by the result window
+    pub fn body_after(&self, sort: &Value) -> Value {
+        let mut body = self.body(0);
+        if let Some(body) = body.as_object_mut() {
+            body.remove("from");
+        }
+        body["search_after"] = sort.clone();
+        body
+    }
 }
 
-pub trait Client {
+/// Searches the documents, the pager runs the searches in the background thread
+pub trait Client: Sync {
     fn execute(&self, request: &SearchRequest) -> Result<Collector<serde_json::Value>, ClientError>;
+
+    /// Requests of the first page which `execute` would send, nothing is sent
+    fn prepare(&self, request: &SearchRequest) -> Result<Vec<PreparedRequest>, ClientError>;
 }
 
 #[derive(Debug, Fail)]
@@ -20,10 +175,102 @@ pub enum ClientError {
     RequestError { inner: String }
 }
 
+impl From<CloudIdError> for ClientError {
+    fn from(err: CloudIdError) -> Self {
+        ClientError::RequestError { inner: format!("invalid cloud ID: {}", err) }
+    }
+}
+
 impl From<FetcherError> for ClientError {
     fn from(err: FetcherError) -> Self {
         match err {
             FetcherError::RequestError { inner } => ClientError::RequestError { inner: inner.clone() }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_body_should_contain_only_query_string_without_range() {
+        let request = SearchRequest { index: "*".to_string(), query: "level: Error".to_string(), range: None, sort: None };
+        assert_eq!(json!({ "query_string": { "query": "level: Error" } }), request.query_body());
+    }
+
+    #[test]
+    fn query_body_should_filter_by_range() {
+        let request = SearchRequest {
+            index: "*".to_string(),
+            query: "*".to_string(),
+            range: Some(TimeRange { field: "@timestamp".to_string(), from: Some("now-1h".to_string()), to: None }),
+            sort: None
+        };
+        assert_eq!(
+            json!({
+                "bool": {
+                    "must": { "query_string": { "query": "*" } },
+                    "filter": { "range": { "@timestamp": { "gte": "now-1h" } } }
+                }
+            }),
+            request.query_body()
+        );
+    }
+
+    #[test]
+    fn body_should_contain_page_and_sort() {
+        let request = SearchRequest {
+            index: "*".to_string(),
+            query: "*".to_string(),
+            range: None,
+            sort: Some(Sort { field: "@timestamp".to_string(), descending: true })
+        };
+        assert_eq!(
+            json!({
+                "size": 10,
+                "from": 20,
+                "query": { "query_string": { "query": "*" } },
+                "sort": [{ "@timestamp": { "order": "desc" } }]
+            }),
+            request.body(20, 10)
+        );
+    }
+
+    #[test]
+    fn body_after_should_replace_offset_by_search_after() {
+        let request = SearchRequest {
+            index: "*".to_string(),
+            query: "*".to_string(),
+            range: None,
+            sort: Some(Sort { field: "@timestamp".to_string(), descending: false })
+        };
+        assert_eq!(
+            json!({
+                "size": 10,
+                "query": { "query_string": { "query": "*" } },
+                "sort": [{ "@timestamp": { "order": "asc" } }],
+                "search_after": [1554076800000_i64]
+            }),
+            PagedSearch::new(&request, 10, None).body_after(&json!([1554076800000_i64]))
+        );
+    }
+
+    #[test]
+    fn paged_search_should_track_total_hits_if_server_supports_it() {
+        let request = SearchRequest { index: "*".to_string(), query: "*".to_string(), range: None, sort: None };
+        let version = |major| ServerVersion { distribution: Distribution::Elasticsearch, number: format!("{}.0.0", major), major, minor: 0 };
+
+        assert_eq!(Some(&json!(true)), PagedSearch::new(&request, 10, Some(&version(7))).body(0).get("track_total_hits"));
+        assert_eq!(None, PagedSearch::new(&request, 10, Some(&version(6))).body(0).get("track_total_hits"));
+        assert_eq!(None, PagedSearch::new(&request, 10, None).body(0).get("track_total_hits"));
+    }
+
+    #[test]
+    fn sort_should_parse_field_and_order() {
+        assert_eq!(Ok(Sort { field: "a.b".to_string(), descending: false }), Sort::parse("a.b"));
+        assert_eq!(Ok(Sort { field: "time:local".to_string(), descending: true }), Sort::parse("time:local:desc"));
+        assert!(Sort::parse("a:up").is_err());
+        assert!(Sort::parse(":desc").is_err());
+    }
+}
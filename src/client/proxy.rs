@@ -0,0 +1,95 @@
+use super::ClientError;
+
+use config::ElasticSearchServer;
+use reqwest::Url;
+
+/// Proxy of the server: the configured one, otherwise the one from the environment variables like curl does.
+/// None if the host of the server is excluded by `no_proxy` of the server or by the `NO_PROXY` variable.
+pub fn resolve_proxy<F>(server: &ElasticSearchServer, env: F) -> Result<Option<String>, ClientError>
+    where F: Fn(&str) -> Option<String>
+{
+    let url = Url::parse(&server.address()?).map_err(|err| {
+        ClientError::RequestError { inner: format!("invalid server address: {}", err) }
+    })?;
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+
+    let env = |names: &[&str]| names.iter()
+        .filter_map(|name| env(name))
+        .find(|value| !value.is_empty());
+    let env_no_proxy = env(&["NO_PROXY", "no_proxy"]).unwrap_or_default();
+    let excluded = server.no_proxy.iter()
+        .map(String::as_str)
+        .chain(env_no_proxy.split(','))
+        .any(|pattern| matches_no_proxy(&host, pattern));
+    if excluded {
+        return Ok(None);
+    }
+
+    Ok(server.proxy.clone().or_else(|| match url.scheme() {
+        "https" => env(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]),
+        _ => env(&["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"])
+    }))
+}
+
+/// `*` matches all hosts, a domain matches itself and its subdomains, with or without the leading dot
+fn matches_no_proxy(host: &str, pattern: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    // ports are ignored, the server is identified by the host
+    let pattern = match pattern.rfind(':') {
+        Some(position) if !pattern.ends_with(']') && pattern.matches(':').count() == 1 => pattern[..position].to_string(),
+        _ => pattern
+    };
+    let domain = pattern.trim_start_matches('.');
+    match pattern.as_str() {
+        "" => false,
+        "*" => true,
+        _ => host == domain || host.ends_with(&format!(".{}", domain))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    fn server(address: &str, proxy: Option<&str>, no_proxy: &[&str]) -> ElasticSearchServer {
+        ElasticSearchServer {
+            name: "test".to_string(),
+            server: address.to_string(),
+            proxy: proxy.map(ToString::to_string),
+            no_proxy: no_proxy.iter().map(ToString::to_string).collect(),
+            ..ElasticSearchServer::default()
+        }
+    }
+
+    fn env(variables: &[(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        let variables: HashMap<&str, &str> = variables.iter().cloned().collect();
+        move |name| variables.get(name).map(ToString::to_string)
+    }
+
+    #[test]
+    fn configured_proxy_should_override_environment() {
+        let server = server("https://es.example.com:9200", Some("http://proxy:3128"), &[]);
+        let env = env(&[("HTTPS_PROXY", "http://other:3128")]);
+        assert_eq!(Some("http://proxy:3128".to_string()), resolve_proxy(&server, env).unwrap());
+    }
+
+    #[test]
+    fn it_should_use_proxy_from_environment_by_scheme() {
+        let env = || env(&[("https_proxy", "http://secure:3128"), ("HTTP_PROXY", ""), ("ALL_PROXY", "http://all:3128")]);
+        assert_eq!(Some("http://secure:3128".to_string()), resolve_proxy(&server("https://es.example.com", None, &[]), env()).unwrap());
+        assert_eq!(Some("http://all:3128".to_string()), resolve_proxy(&server("http://es.example.com", None, &[]), env()).unwrap());
+        assert_eq!(None, resolve_proxy(&server("http://es.example.com", None, &[]), |_: &str| None).unwrap());
+    }
+
+    #[test]
+    fn it_should_connect_directly_to_excluded_hosts() {
+        let env = || env(&[("HTTPS_PROXY", "http://proxy:3128"), ("NO_PROXY", "localhost, .internal:9200")]);
+        assert_eq!(None, resolve_proxy(&server("https://es.internal", None, &[]), env()).unwrap());
+        assert_eq!(None, resolve_proxy(&server("https://LOCALHOST:9200", None, &[]), env()).unwrap());
+        assert_eq!(None, resolve_proxy(&server("https://es.example.com", Some("http://proxy:3128"), &["example.com"]), env()).unwrap());
+        assert_eq!(None, resolve_proxy(&server("https://es.example.com", None, &["*"]), env()).unwrap());
+        assert_eq!(Some("http://proxy:3128".to_string()), resolve_proxy(&server("https://notinternal", None, &[]), env()).unwrap());
+    }
+}
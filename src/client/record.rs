@@ -0,0 +1,219 @@
+use super::{ApiRequest, ClientError, FetcherError, PreparedRequest, Transport};
+
+use config::ElasticSearchServer;
+use openssl::sha::sha256;
+use reqwest::Url;
+use serde_json::Value;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Request and its response or error stored by `--record`, one file per request
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Exchange {
+    request: RecordedRequest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct RecordedRequest {
+    method: String,
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<Value>
+}
+
+impl RecordedRequest {
+    fn of(request: &ApiRequest) -> Self {
+        RecordedRequest { method: request.method.to_string(), path: request.encoded_path(), body: request.body.clone() }
+    }
+
+    /// Name of the file, readable prefix and the hash of the method, the path and the normalised body.
+    /// The keys of the JSON objects are sorted, so the order and the whitespace of the body don't matter.
+    fn file_name(&self) -> String {
+        let body = self.body.as_ref().map(Value::to_string).unwrap_or_default();
+        let hash: String = sha256(format!("{} {}\n{}", self.method, self.path, body).as_bytes()).iter()
+            .take(8)
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let slug: String = self.path.trim_matches('/').chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+            .collect();
+        format!("{}-{}-{}.json", self.method, slug, hash)
+    }
+}
+
+/// Sends the requests by the transport and stores the responses to the directory
+pub struct RecordingTransport {
+    inner: Arc<Transport>,
+    directory: PathBuf
+}
+
+impl RecordingTransport {
+    pub fn create(inner: Arc<Transport>, directory: &Path) -> Result<Self, ClientError> {
+        fs::create_dir_all(directory).map_err(|err| {
+            error!("Cannot create the directory {}: {}", directory.display(), err);
+            ClientError::RequestError { inner: format!("cannot create the directory of the records {}: {}", directory.display(), err) }
+        })?;
+        Ok(RecordingTransport { inner, directory: directory.to_path_buf() })
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn product(&self) -> &'static str {
+        self.inner.product()
+    }
+
+    fn send(&self, request: &ApiRequest) -> Result<Value, FetcherError> {
+        let result = self.inner.send(request);
+        let exchange = Exchange {
+            request: RecordedRequest::of(request),
+            response: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(ToString::to_string)
+        };
+        let path = self.directory.join(exchange.request.file_name());
+        serde_json::to_string_pretty(&exchange)
+            .map_err(|err| err.to_string())
+            .and_then(|content| fs::write(&path, content).map_err(|err| err.to_string()))
+            .map_err(|err| {
+                error!("Cannot record the response to {}: {}", path.display(), err);
+                FetcherError::RequestError { inner: format!("cannot record the response to {}: {}", path.display(), err) }
+            })?;
+        debug!("Recorded {} {} to {}", exchange.request.method, exchange.request.path, path.display());
+        result
+    }
+
+    fn prepare(&self, request: &ApiRequest) -> PreparedRequest {
+        self.inner.prepare(request)
+    }
+}
+
+/// Serves the responses recorded to the directory, the address of the `replay` server
+pub struct ReplayTransport {
+    directory: PathBuf
+}
+
+impl ReplayTransport {
+    pub fn create(server: &ElasticSearchServer) -> Result<Self, ClientError> {
+        let directory = PathBuf::from(&server.server);
+        if !directory.is_dir() {
+            return Err(ClientError::RequestError {
+                inner: format!("directory of the records {} is not found, set it by --address", directory.display())
+            });
+        }
+        Ok(ReplayTransport { directory })
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn product(&self) -> &'static str {
+        "replay"
+    }
+
+    fn send(&self, request: &ApiRequest) -> Result<Value, FetcherError> {
+        let recorded = RecordedRequest::of(request);
+        let path = self.directory.join(recorded.file_name());
+        let not_found = || {
+            let body = recorded.body.as_ref().map(|body| format!(" with the body {}", body)).unwrap_or_default();
+            FetcherError::RequestError {
+                inner: format!("request {} {}{} is not recorded in {}", recorded.method, recorded.path, body, self.directory.display())
+            }
+        };
+        let content = fs::read_to_string(&path).map_err(|_| not_found())?;
+        let exchange: Exchange = serde_json::from_str(&content).map_err(|err| FetcherError::RequestError {
+            inner: format!("invalid record {}: {}", path.display(), err)
+        })?;
+        // the hash matched, the collisions are not expected, but the request is checked anyway
+        if exchange.request != recorded {
+            return Err(not_found());
+        }
+        match exchange {
+            Exchange { error: Some(error), .. } => Err(FetcherError::RequestError { inner: error }),
+            Exchange { response: Some(response), .. } => Ok(response),
+            _ => Err(FetcherError::RequestError { inner: format!("record {} has neither the response nor the error", path.display()) })
+        }
+    }
+
+    fn prepare(&self, request: &ApiRequest) -> PreparedRequest {
+        let path = self.directory.join(RecordedRequest::of(request).file_name());
+        PreparedRequest {
+            api: request.clone(),
+            method: request.method.clone(),
+            url: Url::from_file_path(&path).unwrap_or_else(|_| Url::parse("file:///").expect("valid URL")),
+            headers: vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    struct TestTransport;
+
+    impl Transport for TestTransport {
+        fn product(&self) -> &'static str {
+            "elasticsearch"
+        }
+
+        fn send(&self, request: &ApiRequest) -> Result<Value, FetcherError> {
+            match request.path.last().map(String::as_str) {
+                Some("_search") => Ok(json!({ "hits": { "total": 1, "hits": [{ "_source": { "a": 1 } }] } })),
+                _ => Err(FetcherError::RequestError { inner: "elasticsearch responded 403 Forbidden".to_string() })
+            }
+        }
+
+        fn prepare(&self, request: &ApiRequest) -> PreparedRequest {
+            let url = Url::parse("http://localhost:9200").unwrap().join(&request.encoded_path()).unwrap();
+            PreparedRequest { api: request.clone(), method: request.method.clone(), url, headers: vec![] }
+        }
+    }
+
+    #[test]
+    fn it_should_replay_recorded_responses() {
+        let directory = env::temp_dir().join(format!("elastic-cli-records-{}", std::process::id()));
+        let recording = RecordingTransport::create(Arc::new(TestTransport), &directory).unwrap();
+        let search = ApiRequest::post(&["logs-*", "_search"], json!({ "size": 10, "query": { "match_all": {} } }));
+        let response = recording.send(&search).unwrap();
+        assert!(recording.send(&ApiRequest::get(&[])).is_err());
+
+        let replay = ReplayTransport { directory: directory.clone() };
+        // the order of the keys is normalised
+        let same_search = ApiRequest::post(&["logs-*", "_search"], serde_json::from_str(r#"{"query":{"match_all":{}},"size":10}"#).unwrap());
+        assert_eq!(response, replay.send(&same_search).unwrap());
+        assert_eq!("elasticsearch responded 403 Forbidden", replay.send(&ApiRequest::get(&[])).unwrap_err().to_string());
+
+        let other_search = ApiRequest::post(&["logs-*", "_search"], json!({ "size": 20 }));
+        assert_eq!(
+            format!("request POST /logs-*/_search with the body {{\"size\":20}} is not recorded in {}", directory.display()),
+            replay.send(&other_search).unwrap_err().to_string()
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn it_should_prepare_requests_by_inner_transport() {
+        let directory = env::temp_dir().join(format!("elastic-cli-prepared-{}", std::process::id()));
+        let recording = RecordingTransport::create(Arc::new(TestTransport), &directory).unwrap();
+        let prepared = recording.prepare(&ApiRequest::post(&["logs-*", "_search"], json!({ "size": 10 })));
+        assert_eq!("POST logs-*/_search\n{\n  \"size\": 10\n}", prepared.console());
+        assert_eq!("http://localhost:9200/logs-*/_search", prepared.url.as_str());
+        // nothing is sent, so nothing is recorded
+        assert_eq!(0, fs::read_dir(&directory).unwrap().count());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn it_should_name_files_by_request() {
+        let name = RecordedRequest::of(&ApiRequest::post(&["logs-*", "_search"], json!({}))).file_name();
+        assert!(name.starts_with("POST-logs-___search-"));
+        assert!(name.ends_with(".json"));
+        assert_eq!("GET--", &RecordedRequest::of(&ApiRequest::get(&[])).file_name()[..5]);
+    }
+}
@@ -0,0 +1,192 @@
+use super::{FetcherError, Total};
+
+use serde_json::Value;
+
+/// Search response of Elasticsearch 5 to 8 and OpenSearch
+#[derive(Debug, Deserialize)]
+pub struct SearchResponse {
+    hits: Hits
+}
+
+#[derive(Debug, Deserialize)]
+struct Hits {
+    /// Missing if the counting is disabled by `track_total_hits`
+    #[serde(default)]
+    total: Option<TotalHits>,
+    #[serde(default)]
+    hits: Vec<Hit>
+}
+
+/// `hits.total` is a number before Elasticsearch 7, the newer versions and OpenSearch return an object
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TotalHits {
+    Count(u64),
+    Object {
+        value: u64,
+        #[serde(default)]
+        relation: TotalRelation
+    }
+}
+
+/// Whether the total is exact or the lower bound, the servers stop counting at 10,000 hits by default
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum TotalRelation {
+    #[default]
+    #[serde(rename = "eq")]
+    Equal,
+    #[serde(rename = "gte")]
+    GreaterOrEqual
+}
+
+/// Hit of the search, `_type` of Elasticsearch before 7 is ignored
+#[derive(Debug, Deserialize)]
+struct Hit {
+    #[serde(rename = "_source")]
+    source: Option<Value>,
+    /// Values of the sort fields, returned only for the sorted searches
+    sort: Option<Value>
+}
+
+impl SearchResponse {
+    /// Parses the body of the search response, the error responses are reported by the transports
+    pub fn parse(product: &str, body: Value) -> Result<Self, FetcherError> {
+        serde_json::from_value(body).map_err(|err| {
+            error!("Cannot parse search response from {}: {}", product, err);
+            FetcherError::RequestError { inner: format!("cannot parse search response from {}: {}", product, err) }
+        })
+    }
+
+    /// The total number of documents that matched the search query
+    pub fn total(&self) -> u64 {
+        match self.hits.total {
+            Some(TotalHits::Count(value)) | Some(TotalHits::Object { value, .. }) => value,
+            None => self.hits.hits.len() as u64
+        }
+    }
+
+    pub fn relation(&self) -> TotalRelation {
+        match self.hits.total {
+            Some(TotalHits::Object { relation, .. }) => relation,
+            _ => TotalRelation::Equal
+        }
+    }
+
+    /// Sort values of the last hit, `search_after` of the next page
+    pub fn last_sort(&self) -> Option<Value> {
+        self.hits.hits.last().and_then(|hit| hit.sort.clone())
+    }
+
+    /// Sources of the found documents
+    pub fn documents(self) -> Vec<Value> {
+        self.hits.hits.into_iter()
+            .filter_map(|hit| hit.source)
+            .collect()
+    }
+
+    /// The total and the documents of the page
+    pub fn page(self) -> (Total, Vec<Value>) {
+        let total = Total { value: self.total() as usize, relation: self.relation() };
+        (total, self.documents())
+    }
+}
+
+/// Reason of the error in the body of the response:
+/// `{"error": {"root_cause": [..], "type", "reason"}}` of Elasticsearch and OpenSearch,
+/// `{"error": "..."}` of the old versions, `{"statusCode", "message"}` of Kibana and `{"message"}` of AWS
+pub fn error_reason(body: &Value) -> Option<String> {
+    let described = |error: &Value| {
+        let reason = error.get("reason").and_then(Value::as_str)?;
+        Some(match error.get("type").and_then(Value::as_str) {
+            Some(kind) => format!("{}: {}", kind, reason),
+            None => reason.to_string()
+        })
+    };
+    match body.get("error") {
+        Some(Value::String(error)) => return Some(body.get("message").and_then(Value::as_str).unwrap_or(error).to_string()),
+        Some(error @ Value::Object(_)) => {
+            return error.get("root_cause")
+                .and_then(|causes| causes.get(0))
+                .and_then(described)
+                .or_else(|| described(error))
+                .or_else(|| Some(error.to_string()));
+        },
+        _ => {}
+    }
+    if body.get("hits").is_some() {
+        return None;
+    }
+    body.get("message").or_else(|| body.get("Message"))
+        .and_then(Value::as_str)
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(body: Value) -> Result<SearchResponse, FetcherError> {
+        SearchResponse::parse("elasticsearch", body)
+    }
+
+    #[test]
+    fn it_should_read_total_of_old_elasticsearch() {
+        let response = parse(json!({
+            "took": 1, "hits": { "total": 2, "hits": [{ "_type": "doc", "_source": { "a": 1 } }, { "_type": "doc", "_source": { "a": 2 } }] }
+        })).unwrap();
+        assert_eq!(2, response.total());
+        assert_eq!(TotalRelation::Equal, response.relation());
+        assert_eq!(vec![json!({ "a": 1 }), json!({ "a": 2 })], response.documents());
+    }
+
+    #[test]
+    fn it_should_read_total_of_opensearch() {
+        let response = parse(json!({
+            "took": 1, "hits": { "total": { "value": 10000, "relation": "gte" }, "hits": [{ "_source": { "a": 1 } }, { "_id": "1" }] }
+        })).unwrap();
+        assert_eq!(10000, response.total());
+        assert_eq!(TotalRelation::GreaterOrEqual, response.relation());
+        assert_eq!(vec![json!({ "a": 1 })], response.documents());
+    }
+
+    #[test]
+    fn it_should_read_sort_of_last_hit() {
+        let response = parse(json!({
+            "hits": { "hits": [{ "_source": { "a": 1 }, "sort": [1554076800000_i64] }, { "_source": { "a": 2 }, "sort": [1554076801000_i64] }] }
+        })).unwrap();
+        assert_eq!(Some(json!([1554076801000_i64])), response.last_sort());
+        assert_eq!(None, parse(json!({ "hits": { "hits": [{ "_source": { "a": 1 } }] } })).unwrap().last_sort());
+    }
+
+    #[test]
+    fn it_should_pass_relation_of_total_with_page() {
+        let response = parse(json!({ "hits": { "total": { "value": 10000, "relation": "gte" }, "hits": [{ "_source": { "a": 1 } }] } })).unwrap();
+        assert_eq!(Total { value: 10000, relation: TotalRelation::GreaterOrEqual }, response.page().0);
+    }
+
+    #[test]
+    fn it_should_count_hits_without_total() {
+        let response = parse(json!({ "hits": { "hits": [{ "_source": { "a": 1 } }] } })).unwrap();
+        assert_eq!(1, response.total());
+    }
+
+    #[test]
+    fn it_should_explain_errors() {
+        let error = json!({
+            "error": {
+                "root_cause": [{ "type": "query_shard_exception", "reason": "Failed to parse query [level:]" }],
+                "type": "search_phase_execution_exception",
+                "reason": "all shards failed"
+            },
+            "status": 400
+        });
+        assert_eq!(Some("query_shard_exception: Failed to parse query [level:]".to_string()), error_reason(&error));
+
+        let error = json!({ "error": { "type": "index_not_found_exception", "reason": "no such index [logs]" }, "status": 404 });
+        assert_eq!(Some("index_not_found_exception: no such index [logs]".to_string()), error_reason(&error));
+        let error = json!({ "statusCode": 400, "error": "Bad Request", "message": "Request must contain a kbn-xsrf header." });
+        assert_eq!(Some("Request must contain a kbn-xsrf header.".to_string()), error_reason(&error));
+        assert_eq!(Some("The security token is invalid".to_string()), error_reason(&json!({ "message": "The security token is invalid" })));
+        assert_eq!(None, error_reason(&json!({ "hits": { "hits": [] }, "message": "document field" })));
+    }
+}
@@ -0,0 +1,221 @@
+use super::{FetcherError, error_reason};
+
+use config::RequestConfig;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use reqwest::header::RETRY_AFTER;
+use serde_json::Value;
+
+use std::thread;
+use std::time::Duration;
+
+const BASE_DELAY_MILLIS: u64 = 500;
+const MAX_DELAY_SECS: u64 = 30;
+/// The server may ask to wait longer, but the search should not hang
+const MAX_RETRY_AFTER_SECS: u64 = 120;
+
+/// Retries of the requests with the exponential backoff
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration
+}
+
+/// Failed attempt of the request
+#[derive(Debug, PartialEq)]
+struct Failure {
+    message: String,
+    retryable: bool,
+    retry_after: Option<Duration>
+}
+
+impl RetryPolicy {
+    pub fn new(config: &RequestConfig) -> Self {
+        RetryPolicy {
+            max_retries: config.max_retries(),
+            base_delay: Duration::from_millis(BASE_DELAY_MILLIS),
+            max_delay: Duration::from_secs(MAX_DELAY_SECS)
+        }
+    }
+
+    /// Sends the request built by the function, the transient failures are retried.
+    /// The service is the name of the server type used in the messages.
+    pub fn send<F>(&self, service: &str, request: F) -> Result<Response, FetcherError>
+        where F: Fn() -> RequestBuilder
+    {
+        self.retry(|| Self::attempt(service, request()), thread::sleep)
+    }
+
+    /// Sends the request once, the failures are not logged
+    pub fn send_once(service: &str, request: RequestBuilder) -> Result<Response, FetcherError> {
+        Self::attempt(service, request).map_err(|failure| FetcherError::RequestError { inner: failure.message })
+    }
+
+    fn attempt(service: &str, request: RequestBuilder) -> Result<Response, Failure> {
+        let mut response = request.send().map_err(|err| Failure {
+            message: format!("cannot read response from {}: {}", service, err),
+            // timeouts and connection errors
+            retryable: err.is_timeout() || err.is_http(),
+            retry_after: None
+        })?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let retry_after = response.headers().get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, Utc::now()));
+        let reason = response.json::<Value>().ok()
+            .and_then(|body| error_reason(&body))
+            .map(|reason| format!(": {}", reason))
+            .unwrap_or_default();
+        Err(Failure {
+            message: format!("{} responded {}{}", service, status, reason),
+            retryable: is_retryable(status),
+            retry_after
+        })
+    }
+
+    fn retry<T, A, S>(&self, attempt: A, sleep: S) -> Result<T, FetcherError>
+        where A: Fn() -> Result<T, Failure>, S: Fn(Duration)
+    {
+        let mut retry = 0;
+        loop {
+            match attempt() {
+                Ok(result) => return Ok(result),
+                Err(failure) => {
+                    if !failure.retryable || retry >= self.max_retries {
+                        let message = if retry > 0 {
+                            format!("{} after {} retries", failure.message, retry)
+                        } else {
+                            failure.message
+                        };
+                        error!("Request failed: {}", message);
+                        return Err(FetcherError::RequestError { inner: message });
+                    }
+                    let delay = match failure.retry_after {
+                        Some(retry_after) => retry_after.min(Duration::from_secs(MAX_RETRY_AFTER_SECS)),
+                        None => self.delay(retry, rand::thread_rng().gen_range(0.5, 1.0))
+                    };
+                    retry += 1;
+                    warn!("Request failed: {}, retry {} of {} in {} ms", failure.message, retry, self.max_retries, as_millis(delay));
+                    sleep(delay);
+                }
+            }
+        }
+    }
+
+    /// Delay before the retry, doubled after each retry, the jitter is the multiplier between 0.5 and 1
+    fn delay(&self, retry: u32, jitter: f64) -> Duration {
+        let exponential = as_millis(self.base_delay).saturating_mul(1u64.checked_shl(retry).unwrap_or(u64::MAX));
+        let delay = exponential.min(as_millis(self.max_delay));
+        Duration::from_millis((delay as f64 * jitter) as u64)
+    }
+}
+
+/// Too many requests, or the gateway cannot reach the overloaded or restarting node
+fn is_retryable(status: StatusCode) -> bool {
+    matches!(status, StatusCode::TOO_MANY_REQUESTS | StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT)
+}
+
+/// `Retry-After` is either the number of seconds or the HTTP date
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    DateTime::parse_from_rfc2822(value).ok()
+        .map(|date| (date.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
+fn as_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+    use std::cell::{Cell, RefCell};
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy::new(&RequestConfig { max_retries: Some(max_retries), ..RequestConfig::default() })
+    }
+
+    fn failure(retryable: bool, retry_after: Option<Duration>) -> Failure {
+        Failure { message: "elasticsearch responded 503 Service Unavailable".to_string(), retryable, retry_after }
+    }
+
+    #[test]
+    fn it_should_double_delay_up_to_the_limit() {
+        let policy = policy(3);
+        assert_eq!(Duration::from_millis(500), policy.delay(0, 1.0));
+        assert_eq!(Duration::from_millis(2000), policy.delay(2, 1.0));
+        assert_eq!(Duration::from_millis(1000), policy.delay(2, 0.5));
+        assert_eq!(Duration::from_secs(30), policy.delay(10, 1.0));
+        assert_eq!(Duration::from_secs(30), policy.delay(100, 1.0));
+    }
+
+    #[test]
+    fn it_should_retry_transient_failures() {
+        let attempts = Cell::new(0);
+        let delays = RefCell::new(vec![]);
+        let result = policy(3).retry(|| {
+            attempts.set(attempts.get() + 1);
+            match attempts.get() {
+                1 => Err(failure(true, Some(Duration::from_secs(7)))),
+                2 => Err(failure(true, None)),
+                _ => Ok("response")
+            }
+        }, |delay| delays.borrow_mut().push(delay));
+
+        assert_eq!("response", result.unwrap());
+        let delays = delays.borrow();
+        assert_eq!(Duration::from_secs(7), delays[0]);
+        assert!(delays[1] >= Duration::from_millis(500) && delays[1] <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn it_should_fail_after_max_retries() {
+        let attempts = Cell::new(0);
+        let result: Result<(), FetcherError> = policy(2).retry(|| {
+            attempts.set(attempts.get() + 1);
+            Err(failure(true, Some(Duration::from_secs(0))))
+        }, |_| ());
+
+        assert_eq!(3, attempts.get());
+        assert_eq!("elasticsearch responded 503 Service Unavailable after 2 retries", result.unwrap_err().to_string());
+    }
+
+    #[test]
+    fn it_should_not_retry_permanent_failures() {
+        let attempts = Cell::new(0);
+        let result: Result<(), FetcherError> = policy(2).retry(|| {
+            attempts.set(attempts.get() + 1);
+            Err(failure(false, None))
+        }, |_| panic!("should not wait"));
+
+        assert_eq!(1, attempts.get());
+        assert_eq!("elasticsearch responded 503 Service Unavailable", result.unwrap_err().to_string());
+    }
+
+    #[test]
+    fn it_should_parse_retry_after_as_seconds_or_date() {
+        let now = Utc.ymd(2015, 10, 21).and_hms(7, 27, 0);
+        assert_eq!(Some(Duration::from_secs(120)), parse_retry_after("120", now));
+        assert_eq!(Some(Duration::from_secs(60)), parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now));
+        assert_eq!(Some(Duration::from_secs(0)), parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now));
+        assert_eq!(None, parse_retry_after("soon", now));
+    }
+
+    #[test]
+    fn it_should_retry_only_transient_statuses() {
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::GATEWAY_TIMEOUT));
+        assert!(!is_retryable(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+    }
+}
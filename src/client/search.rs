@@ -0,0 +1,182 @@
+use super::{
+    ApiRequest, Client, ClientError, Collector, Fetcher, FetcherError, PagedSearch, PreparedRequest, RecordingTransport, SearchRequest,
+    SearchResponse, Total, Transport, VersionProbe, create_transport
+};
+
+use config::{ElasticSearchServer, SecretsReader};
+use serde_json::Value;
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Search of the documents, the transport of the server type sends the requests
+pub struct SearchClient {
+    secrets: Arc<SecretsReader>,
+    server_config: ElasticSearchServer,
+    buffer_size: usize,
+    /// Directory of `--record`, the responses are stored to it
+    record: Option<PathBuf>,
+    version: VersionProbe
+}
+
+pub struct SearchFetcher {
+    transport: Arc<Transport>,
+    search: PagedSearch,
+    /// Sort values of the last fetched hit, the follow mode asks for the hits after it
+    last_sort: Mutex<Option<Value>>
+}
+
+impl SearchClient {
+    pub fn create(
+        secrets: Arc<SecretsReader>,
+        server_config: ElasticSearchServer,
+        buffer_size: usize,
+        record: Option<PathBuf>,
+        version: VersionProbe
+    ) -> Self {
+        // the version request is recorded with the searches, so the replay detects the same version without the cache
+        let version = if record.is_some() { version.uncached() } else { version };
+        SearchClient { secrets, server_config, buffer_size, record, version }
+    }
+
+    fn search(&self, transport: Arc<Transport>, request: &SearchRequest) -> Result<Collector<Value>, ClientError> {
+        let version = self.version.get(|| transport.send(&ApiRequest::get(&[]).without_retries()));
+        let search = PagedSearch::new(request, self.buffer_size, version.as_ref());
+
+        Collector::create(SearchFetcher { transport, search, last_sort: Mutex::new(None) })
+            .map_err(From::from)
+    }
+
+    fn transport(&self) -> Result<Arc<Transport>, ClientError> {
+        let transport = create_transport(&self.server_config, self.secrets.as_ref())?;
+        match &self.record {
+            Some(directory) => Ok(Arc::new(RecordingTransport::create(transport, directory)?)),
+            None => Ok(transport)
+        }
+    }
+}
+
+impl Client for SearchClient {
+    fn execute(&self, request: &SearchRequest) -> Result<Collector<Value>, ClientError> {
+        let transport = self.transport()?;
+        self.search(transport, request)
+    }
+
+    fn prepare(&self, request: &SearchRequest) -> Result<Vec<PreparedRequest>, ClientError> {
+        let transport = create_transport(&self.server_config, self.secrets.as_ref())?;
+        let version = self.version.current();
+        let mut requests = Vec::new();
+        if version.is_none() {
+            warn!("The version of the server is unknown, the search depends on the response of 'GET /'");
+            warn!("Hint: use option --server-version, e.g. 'elastic-cli --dry-run search --server-version 7.17.0 ...'");
+            requests.push(transport.prepare(&ApiRequest::get(&[])));
+        }
+        let search = PagedSearch::new(request, self.buffer_size, version.as_ref());
+        requests.push(transport.prepare(&SearchFetcher::page_request(&search, 0)));
+        Ok(requests)
+    }
+}
+
+impl SearchFetcher {
+    fn page_request(search: &PagedSearch, from: usize) -> ApiRequest {
+        ApiRequest::post(&[&search.request.index, "_search"], search.body(from))
+    }
+
+    /// Sends the search, the sort values of its last hit are kept for the follow mode
+    fn fetch(&self, request: &ApiRequest) -> Result<(Total, Vec<Value>), FetcherError> {
+        let product = self.transport.product();
+        let response = self.transport.send(request)
+            .and_then(|body| SearchResponse::parse(product, body))?;
+        if let Some(sort) = response.last_sort() {
+            *self.last_sort.lock().unwrap_or_else(|err| err.into_inner()) = Some(sort);
+        }
+        Ok(response.page())
+    }
+}
+
+impl Fetcher<Value> for SearchFetcher {
+    fn fetch_next(&self, from: usize) -> Result<(Total, Vec<Value>), FetcherError> {
+        self.fetch(&Self::page_request(&self.search, from))
+    }
+
+    /// The hits after the last fetched one by `search_after`, the unsorted searches have no sort values and are paged
+    fn fetch_new(&self, from: usize) -> Result<(Total, Vec<Value>), FetcherError> {
+        let last_sort = self.last_sort.lock().unwrap_or_else(|err| err.into_inner()).clone();
+        match last_sort {
+            Some(sort) => {
+                let body = self.search.body_after(&sort);
+                self.fetch(&ApiRequest::post(&[&self.search.request.index, "_search"], body))
+            },
+            None => self.fetch_next(from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use client::{Distribution, ServerVersion, TotalRelation, VersionCache};
+    use config::{ElasticSearchServerType, SystemSecretsStorage};
+    use reqwest::Url;
+
+    use std::env;
+    use std::fs;
+
+    /// Elasticsearch 7 which counts all hits only with `track_total_hits`
+    struct TestTransport;
+
+    impl Transport for TestTransport {
+        fn product(&self) -> &'static str {
+            "elasticsearch"
+        }
+
+        fn send(&self, request: &ApiRequest) -> Result<Value, FetcherError> {
+            if request.path.is_empty() {
+                return Ok(json!({ "version": { "number": "7.17.0" } }));
+            }
+            let relation = match request.body.as_ref().and_then(|body| body.get("track_total_hits")) {
+                Some(_) => "eq",
+                None => "gte"
+            };
+            Ok(json!({ "hits": { "total": { "value": 1, "relation": relation }, "hits": [{ "_source": { "a": 1 } }] } }))
+        }
+
+        fn prepare(&self, request: &ApiRequest) -> PreparedRequest {
+            let url = Url::parse("http://localhost:9200").unwrap().join(&request.encoded_path()).unwrap();
+            PreparedRequest { api: request.clone(), method: request.method.clone(), url, headers: vec![] }
+        }
+    }
+
+    #[test]
+    fn it_should_replay_recording_made_with_cached_version() {
+        let directory = env::temp_dir().join(format!("elastic-cli-recorded-search-{}", std::process::id()));
+        let versions = env::temp_dir().join(format!("elastic-cli-recorded-versions-{}", std::process::id()));
+        let secrets = || Arc::new(SystemSecretsStorage::new("elastic-cli"));
+        let request = SearchRequest { index: "logs-*".to_string(), query: "*".to_string(), range: None, sort: None };
+
+        let server = ElasticSearchServer { name: "logs".to_string(), server: "http://logs:9200".to_string(), ..ElasticSearchServer::default() };
+        VersionCache::new(versions.clone(), &server).store(&ServerVersion::from_number(Distribution::Elasticsearch, "7.17.0").unwrap());
+        let probe = VersionProbe::cached(VersionCache::new(versions.clone(), &server));
+        let recording = SearchClient::create(secrets(), server, 10, Some(directory.clone()), probe);
+        let transport = Arc::new(RecordingTransport::create(Arc::new(TestTransport), &directory).unwrap());
+        let mut recorded = recording.search(transport, &request).unwrap();
+        assert_eq!(TotalRelation::Equal, recorded.total_relation);
+
+        // the version of the replay server is not cached yet
+        let replay_server = ElasticSearchServer {
+            name: "replay".to_string(),
+            server: directory.to_string_lossy().to_string(),
+            server_type: ElasticSearchServerType::Replay,
+            ..ElasticSearchServer::default()
+        };
+        let probe = VersionProbe::cached(VersionCache::new(versions.clone(), &replay_server));
+        let replay = SearchClient::create(secrets(), replay_server, 10, None, probe);
+        let mut replayed = replay.execute(&request).unwrap();
+        assert_eq!(TotalRelation::Equal, replayed.total_relation);
+        assert_eq!(recorded.iter().collect::<Vec<_>>(), replayed.iter().collect::<Vec<_>>());
+
+        fs::remove_dir_all(directory).unwrap();
+        fs::remove_file(versions).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+use super::AwsCredentials;
+
+use chrono::{DateTime, Utc};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sha::sha256;
+use openssl::sign::Signer;
+use reqwest::Url;
+
+use std::collections::BTreeMap;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Signs the requests by AWS Signature Version 4
+#[derive(Clone, Debug)]
+pub struct AwsSigner {
+    credentials: AwsCredentials,
+    region: String,
+    service: String
+}
+
+impl AwsSigner {
+    pub fn new(credentials: AwsCredentials, region: &str, service: &str) -> Self {
+        AwsSigner { credentials, region: region.to_string(), service: service.to_string() }
+    }
+
+    /// Headers which sign the request, `Host` is not included, it is set by the HTTP client
+    pub fn sign(&self, method: &str, url: &Url, body: &[u8], now: DateTime<Utc>) -> Vec<(String, String)> {
+        let payload_hash = hex(&sha256(body));
+        let mut headers = BTreeMap::new();
+        headers.insert("host".to_string(), host(url));
+        headers.insert("x-amz-date".to_string(), now.format("%Y%m%dT%H%M%SZ").to_string());
+        headers.insert("x-amz-content-sha256".to_string(), payload_hash.clone());
+        if let Some(token) = &self.credentials.session_token {
+            headers.insert("x-amz-security-token".to_string(), token.clone());
+        }
+        let authorization = self.authorization(method, url, &headers, &payload_hash, now);
+        headers.remove("host");
+        headers.into_iter()
+            .chain(Some(("authorization".to_string(), authorization)))
+            .collect()
+    }
+
+    /// Value of the `Authorization` header, the header names are lowercase
+    fn authorization(&self, method: &str, url: &Url, headers: &BTreeMap<String, String>, payload_hash: &str, now: DateTime<Utc>) -> String {
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+        let signed_headers = headers.keys().map(String::as_str).collect::<Vec<_>>().join(";");
+        let canonical_headers: String = headers.iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.split_whitespace().collect::<Vec<_>>().join(" ")))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, uri_encode(url.path(), false), canonical_query(url), canonical_headers, signed_headers, payload_hash
+        );
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM, now.format("%Y%m%dT%H%M%SZ"), scope, hex(&sha256(canonical_request.as_bytes()))
+        );
+        let signature = hex(&hmac(&self.signing_key(&date), string_to_sign.as_bytes()));
+        format!("{} Credential={}/{}, SignedHeaders={}, Signature={}",
+                ALGORITHM, self.credentials.access_key_id, scope, signed_headers, signature)
+    }
+
+    fn signing_key(&self, date: &str) -> Vec<u8> {
+        let secret = format!("AWS4{}", self.credentials.secret_access_key);
+        [date, self.region.as_str(), self.service.as_str(), "aws4_request"].iter()
+            .fold(secret.into_bytes(), |key, part| hmac(&key, part.as_bytes()))
+    }
+}
+
+fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string()
+    }
+}
+
+/// Decoded query parameters encoded again and sorted
+fn canonical_query(url: &Url) -> String {
+    let mut parameters: Vec<(String, String)> = url.query_pairs()
+        .map(|(name, value)| (uri_encode(&name, true), uri_encode(&value, true)))
+        .collect();
+    parameters.sort();
+    parameters.iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Percent encoding of everything except the unreserved characters, the path keeps the slashes
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    value.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{:02X}", byte)
+        })
+        .collect()
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length, the errors are not possible
+    let key = PKey::hmac(key).expect("HMAC key");
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).expect("HMAC signer");
+    signer.update(data).expect("HMAC update");
+    signer.sign_to_vec().expect("HMAC signature")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    // the test vectors of the AWS Signature Version 4 test suite
+    fn signer(service: &str) -> AwsSigner {
+        AwsSigner::new(AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None
+        }, "us-east-1", service)
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.ymd(2015, 8, 30).and_hms(12, 36, 0)
+    }
+
+    fn authorization(method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> String {
+        let url = Url::parse(url).unwrap();
+        let mut headers: BTreeMap<String, String> = headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        headers.insert("host".to_string(), host(&url));
+        headers.insert("x-amz-date".to_string(), "20150830T123600Z".to_string());
+        signer("service").authorization(method, &url, &headers, &hex(&sha256(body)), now())
+    }
+
+    fn signature(authorization: &str) -> &str {
+        authorization.rsplit("Signature=").next().unwrap()
+    }
+
+    #[test]
+    fn it_should_derive_signing_key() {
+        let signer = AwsSigner::new(AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None
+        }, "us-east-1", "iam");
+        assert_eq!("f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d", hex(&signer.signing_key("20120215")));
+    }
+
+    #[test]
+    fn it_should_sign_get_vanilla() {
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31",
+            authorization("GET", "http://example.amazonaws.com/", &[], b"")
+        );
+    }
+
+    #[test]
+    fn it_should_sign_sorted_query_parameters() {
+        assert_eq!("b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500",
+                   signature(&authorization("GET", "http://example.amazonaws.com/?Param2=value2&Param1=value1", &[], b"")));
+    }
+
+    #[test]
+    fn it_should_sign_post_vanilla() {
+        assert_eq!("5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b",
+                   signature(&authorization("POST", "http://example.amazonaws.com/", &[], b"")));
+    }
+
+    #[test]
+    fn it_should_sign_body() {
+        let headers = [("content-type", "application/x-www-form-urlencoded")];
+        assert_eq!("ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a",
+                   signature(&authorization("POST", "http://example.amazonaws.com/", &headers, b"Param1=value1")));
+    }
+
+    #[test]
+    fn it_should_add_signing_headers() {
+        let mut signer = signer("es");
+        signer.credentials.session_token = Some("session".to_string());
+        let headers = signer.sign("POST", &Url::parse("https://search.eu.es.amazonaws.com/logs-*/_search").unwrap(), b"{}", now());
+        let names: Vec<&str> = headers.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(vec!["x-amz-content-sha256", "x-amz-date", "x-amz-security-token", "authorization"], names);
+        assert!(headers[3].1.contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date;x-amz-security-token,"));
+        assert_eq!("/logs-%2A/_search", uri_encode("/logs-*/_search", false));
+    }
+}
@@ -1,4 +1,4 @@
-use client::{Client, SearchRequest, Collector, ClientError, FetcherError};
+use client::{Client, SearchRequest, Collector, ClientError, FetcherError, PreparedRequest, Total};
 use serde_json::Value;
 use client::fetcher::Fetcher;
 
@@ -24,6 +24,10 @@ impl Client for StubClient {
         Collector::create(StubFetcher::new(self.buffer_size, 1000))
             .map_err(From::from)
     }
+
+    fn prepare(&self, _: &SearchRequest) -> Result<Vec<PreparedRequest>, ClientError> {
+        Ok(vec![])
+    }
 }
 
 impl StubFetcher {
@@ -40,10 +44,10 @@ impl StubFetcher {
 
 impl Fetcher<Value> for StubFetcher {
 
-    fn fetch_next(&self, from: usize) -> Result<(usize, Vec<Value>), FetcherError> {
+    fn fetch_next(&self, from: usize) -> Result<(Total, Vec<Value>), FetcherError> {
         std::thread::sleep(std::time::Duration::from_millis(200));
         let to = std::cmp::min(from + self.buffer_size, self.total_count);
-        Ok((self.total_count, (from..to).map(|i| json!({
+        Ok((self.total_count.into(), (from..to).map(|i| json!({
             "index": i,
             "pow": i * i,
             "name": format!("Item #{}", i)
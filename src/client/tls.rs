@@ -0,0 +1,165 @@
+use config::TlsConfig;
+
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use openssl::stack::Stack;
+use openssl::x509::X509;
+use reqwest::{Certificate, ClientBuilder, Identity};
+
+use std::fs;
+
+#[derive(Debug, Fail, PartialEq)]
+#[fail(display = "{}: {}", path, inner)]
+pub struct TlsError {
+    path: String,
+    inner: String
+}
+
+impl TlsError {
+    fn new(path: &str, inner: impl ToString) -> Self {
+        TlsError { path: path.to_string(), inner: inner.to_string() }
+    }
+}
+
+/// Adds the CA and the client certificates to the HTTP client, fails if the files cannot be read
+pub fn configure_tls(builder: ClientBuilder, tls: &TlsConfig, client_cert_password: &str) -> Result<ClientBuilder, TlsError> {
+    let mut builder = builder;
+    if let Some(path) = &tls.ca_cert {
+        builder = builder.add_root_certificate(load_certificate(path)?);
+    }
+    match (&tls.client_cert, &tls.client_key) {
+        (Some(cert_path), key_path) => {
+            builder = builder.identity(load_identity(cert_path, key_path.as_deref(), client_cert_password)?);
+        },
+        (None, Some(key_path)) => return Err(TlsError::new(key_path, "the client key is set without the client certificate")),
+        (None, None) => {}
+    }
+    if tls.insecure_skip_verify {
+        builder = builder
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true);
+    }
+    Ok(builder)
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, TlsError> {
+    fs::read(path).map_err(|err| TlsError::new(path, err))
+}
+
+fn is_pem(data: &[u8]) -> bool {
+    String::from_utf8_lossy(data).contains("-----BEGIN ")
+}
+
+fn load_certificate(path: &str) -> Result<Certificate, TlsError> {
+    let data = read_file(path)?;
+    let certificate = if is_pem(&data) { Certificate::from_pem(&data) } else { Certificate::from_der(&data) };
+    certificate.map_err(|err| TlsError::new(path, format!("cannot read the certificate: {}", err)))
+}
+
+/// PEM certificates are converted to PKCS#12, the only format of the client certificates supported by the native TLS
+fn load_identity(cert_path: &str, key_path: Option<&str>, password: &str) -> Result<Identity, TlsError> {
+    let cert = read_file(cert_path)?;
+    let (pkcs12, password) = if is_pem(&cert) {
+        let key = match key_path {
+            Some(key_path) => read_file(key_path)?,
+            None => cert.clone()
+        };
+        let pkcs12 = pem_to_pkcs12(&cert, &key).map_err(|err| TlsError::new(cert_path, err))?;
+        (pkcs12, "")
+    } else if let Some(key_path) = key_path {
+        return Err(TlsError::new(key_path, "the key of the PKCS#12 certificate must be inside of the certificate file"));
+    } else {
+        (cert, password)
+    };
+    Identity::from_pkcs12_der(&pkcs12, password)
+        .map_err(|err| TlsError::new(cert_path, format!("cannot read the client certificate: {}", err)))
+}
+
+/// Packs the first certificate of the PEM chain with the key, the rest of the chain is kept as the CA certificates
+fn pem_to_pkcs12(cert: &[u8], key: &[u8]) -> Result<Vec<u8>, String> {
+    let mut chain = X509::stack_from_pem(cert)
+        .map_err(|err| format!("cannot read the PEM certificate: {}", err))?
+        .into_iter();
+    let cert = chain.next().ok_or_else(|| "there is no certificate in the file".to_string())?;
+    let key = PKey::private_key_from_pem(key)
+        .map_err(|err| format!("cannot read the private key: {}", err))?;
+    let mut ca = Stack::new().map_err(|err| err.to_string())?;
+    for cert in chain {
+        ca.push(cert).map_err(|err| err.to_string())?;
+    }
+    Pkcs12::builder()
+        .name("elastic-cli")
+        .pkey(&key)
+        .cert(&cert)
+        .ca(ca)
+        .build2("")
+        .and_then(|pkcs12| pkcs12.to_der())
+        .map_err(|err| format!("cannot convert the certificate to PKCS#12: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::rsa::Rsa;
+    use openssl::x509::X509NameBuilder;
+
+    use std::env;
+    use std::path::PathBuf;
+
+    /// Writes the self-signed certificate and its key to the temporary directory
+    fn write_certificate(name: &str) -> (PathBuf, PathBuf) {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_text("CN", "localhost").unwrap();
+        let subject = subject.build();
+        let mut cert = X509::builder().unwrap();
+        cert.set_version(2).unwrap();
+        cert.set_subject_name(&subject).unwrap();
+        cert.set_issuer_name(&subject).unwrap();
+        cert.set_pubkey(&key).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+
+        let dir = env::temp_dir();
+        let cert_path = dir.join(format!("elastic-cli-{}-{}.pem", name, std::process::id()));
+        let key_path = dir.join(format!("elastic-cli-{}-{}.key", name, std::process::id()));
+        fs::write(&cert_path, cert.build().to_pem().unwrap()).unwrap();
+        fs::write(&key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        (cert_path, key_path)
+    }
+
+    fn tls(ca_cert: Option<&PathBuf>, client_cert: Option<&PathBuf>, client_key: Option<&PathBuf>) -> TlsConfig {
+        let path = |path: Option<&PathBuf>| path.map(|path| path.to_string_lossy().to_string());
+        TlsConfig {
+            ca_cert: path(ca_cert),
+            client_cert: path(client_cert),
+            client_key: path(client_key),
+            insecure_skip_verify: false
+        }
+    }
+
+    #[test]
+    fn it_should_load_pem_certificates() {
+        let (cert_path, key_path) = write_certificate("pem");
+        let tls = tls(Some(&cert_path), Some(&cert_path), Some(&key_path));
+        assert!(configure_tls(ClientBuilder::new(), &tls, "").is_ok());
+    }
+
+    #[test]
+    fn it_should_fail_on_missing_key() {
+        let (cert_path, _) = write_certificate("no-key");
+        let tls = tls(None, Some(&cert_path), None);
+        let err = configure_tls(ClientBuilder::new(), &tls, "").err().unwrap();
+        assert!(err.to_string().contains("cannot read the private key"));
+    }
+
+    #[test]
+    fn it_should_fail_on_missing_files_and_key_without_certificate() {
+        let missing = PathBuf::from("/nonexistent/ca.pem");
+        assert!(configure_tls(ClientBuilder::new(), &tls(Some(&missing), None, None), "").is_err());
+        assert!(configure_tls(ClientBuilder::new(), &tls(None, None, Some(&missing)), "").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use super::{
+    ClientError, FetcherError, PreparedRequest, ReplayTransport, RequestAuth, RetryPolicy, error_reason, http_client, redacted_headers
+};
+use super::direct::DirectTransport;
+use super::kibana::KibanaProxyTransport;
+
+use config::{ElasticSearchServer, ElasticSearchServerType, SecretsReader};
+use reqwest::{Method, RequestBuilder, Url};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::Value;
+
+use std::sync::Arc;
+
+/// Request to the API of Elasticsearch, the path is relative to the address of the server
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiRequest {
+    pub method: Method,
+    /// Segments of the path, encoded by the transports, e.g. `["logs-*", "_search"]`
+    pub path: Vec<String>,
+    pub body: Option<Value>,
+    /// The transient failures are retried, optional requests fail at once
+    pub retry: bool
+}
+
+impl ApiRequest {
+    pub fn get(path: &[&str]) -> Self {
+        ApiRequest::new(Method::GET, path, None)
+    }
+
+    pub fn post(path: &[&str], body: Value) -> Self {
+        ApiRequest::new(Method::POST, path, Some(body))
+    }
+
+    fn new(method: Method, path: &[&str], body: Option<Value>) -> Self {
+        ApiRequest {
+            method,
+            path: path.iter().map(ToString::to_string).collect(),
+            body,
+            retry: true
+        }
+    }
+
+    pub fn without_retries(self) -> Self {
+        ApiRequest { retry: false, ..self }
+    }
+
+    /// Percent encoded path starting with the slash
+    pub fn encoded_path(&self) -> String {
+        let mut url = Url::parse("http://localhost/").expect("valid URL");
+        url.path_segments_mut().expect("URL with the path").pop_if_empty().extend(&self.path);
+        url.path().to_string()
+    }
+}
+
+/// Sends the API requests to the server, the commands build on it regardless of the server type
+pub trait Transport: Send + Sync {
+    /// Name of the server type used in the messages
+    fn product(&self) -> &'static str;
+
+    /// Sends the request and decodes the JSON response, the error responses are reported with their reasons
+    fn send(&self, request: &ApiRequest) -> Result<Value, FetcherError>;
+
+    /// The HTTP request which `send` would send, for `--dry-run`
+    fn prepare(&self, request: &ApiRequest) -> PreparedRequest;
+}
+
+/// Transport of the server type
+pub fn create_transport(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<Arc<Transport>, ClientError> {
+    match server.server_type {
+        ElasticSearchServerType::Elastic | ElasticSearchServerType::OpenSearch =>
+            Ok(Arc::new(DirectTransport::create(server, secrets)?)),
+        ElasticSearchServerType::Kibana | ElasticSearchServerType::OpenSearchDashboards =>
+            Ok(Arc::new(KibanaProxyTransport::create(server, secrets)?)),
+        ElasticSearchServerType::Replay => Ok(Arc::new(ReplayTransport::create(server)?)),
+        ElasticSearchServerType::File | ElasticSearchServerType::Stub => Err(ClientError::RequestError {
+            inner: format!("the {} server does not support the API requests", server.server_type.product_name())
+        })
+    }
+}
+
+/// Connection shared by the transports: the HTTP client with TLS, the proxy and the headers of the server,
+/// the authentication and the retries
+pub struct Connection {
+    client: reqwest::Client,
+    auth: RequestAuth,
+    retry: RetryPolicy,
+    product: &'static str,
+    /// Custom headers of the server with the secrets redacted
+    redacted_headers: Vec<(String, String)>
+}
+
+impl Connection {
+    pub fn create(server: &ElasticSearchServer, secrets: &SecretsReader) -> Result<Self, ClientError> {
+        Ok(Connection {
+            client: http_client(server, secrets)?,
+            auth: RequestAuth::create(server, secrets)?,
+            retry: RetryPolicy::new(&server.request),
+            product: server.server_type.product_name(),
+            redacted_headers: redacted_headers(server)
+        })
+    }
+
+    pub fn product(&self) -> &'static str {
+        self.product
+    }
+
+    /// Sends the API request to the URL with the extra headers, the method may differ from the one of the API request,
+    /// e.g. the console proxy accepts only POST
+    pub fn send(&self, request: &ApiRequest, method: Method, url: &Url, headers: &[(&str, &str)]) -> Result<Value, FetcherError> {
+        let retry = request.retry;
+        let body = request.body.as_ref().map(Value::to_string);
+        let build = || -> RequestBuilder {
+            let payload = body.as_ref().map(String::as_bytes).unwrap_or_default();
+            let mut request = self.auth.apply(self.client.request(method.clone(), url.clone()), method.as_str(), url, payload);
+            for (name, value) in headers {
+                request = request.header(*name, *value);
+            }
+            match &body {
+                Some(body) => request.header(CONTENT_TYPE, "application/json").body(body.clone()),
+                None => request
+            }
+        };
+        let mut response = if retry {
+            self.retry.send(self.product, build)?
+        } else {
+            RetryPolicy::send_once(self.product, build())?
+        };
+
+        let value = response.json::<Value>().map_err(|err| {
+            if retry {
+                error!("Cannot parse json response from {}: {}", self.product, err);
+            }
+            FetcherError::RequestError { inner: format!("cannot parse json response from {}: {}", self.product, err) }
+        })?;
+        // the proxies may return the errors of Elasticsearch with the success status
+        match value.get("error").and_then(|_| error_reason(&value)) {
+            Some(reason) => {
+                if retry {
+                    error!("Request to {} failed: {}", self.product, reason);
+                }
+                Err(FetcherError::RequestError { inner: format!("{} responded with error: {}", self.product, reason) })
+            },
+            None => Ok(value)
+        }
+    }
+
+    /// The request which `send` would send, the headers of the HTTP client are listed too
+    pub fn prepare(&self, request: &ApiRequest, method: Method, url: &Url, headers: &[(&str, &str)]) -> PreparedRequest {
+        let body = request.body.as_ref().map(Value::to_string).unwrap_or_default();
+        let mut prepared_headers = self.redacted_headers.clone();
+        prepared_headers.extend(self.auth.redacted(method.as_str(), url, body.as_bytes()));
+        prepared_headers.extend(headers.iter().map(|(name, value)| (name.to_string(), value.to_string())));
+        if request.body.is_some() {
+            prepared_headers.push((CONTENT_TYPE.to_string(), "application/json".to_string()));
+        }
+        PreparedRequest { api: request.clone(), method, url: url.clone(), headers: prepared_headers }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_encode_path_segments() {
+        assert_eq!("/logs-*/_search", ApiRequest::post(&["logs-*", "_search"], json!({})).encoded_path());
+        assert_eq!("/%3Clogs-%7Bnow%2Fd%7D%3E/_search", ApiRequest::get(&["<logs-{now/d}>", "_search"]).encoded_path());
+        assert_eq!("/", ApiRequest::get(&[]).encoded_path());
+    }
+}
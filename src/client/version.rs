@@ -0,0 +1,222 @@
+use super::FetcherError;
+
+use config::ElasticSearchServer;
+use serde_json::{Map, Value};
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Distribution of the server, OpenSearch reports it in `version.distribution`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    Elasticsearch,
+    OpenSearch
+}
+
+/// Version of the server reported by `GET /`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerVersion {
+    pub distribution: Distribution,
+    pub number: String,
+    pub major: u32,
+    pub minor: u32
+}
+
+impl ServerVersion {
+    /// Reads the version from the body of `GET /`, None for the unknown responses
+    pub fn parse(body: &Value) -> Option<Self> {
+        let version = body.get("version")?;
+        let number = version.get("number")?.as_str()?;
+        let distribution = match version.get("distribution").and_then(Value::as_str) {
+            Some("opensearch") => Distribution::OpenSearch,
+            _ => Distribution::Elasticsearch
+        };
+        Self::from_number(distribution, number)
+    }
+
+    /// Reads the version number, e.g. `7.17.0` or `8.0.0-SNAPSHOT`, None if it does not start with the major version
+    pub fn from_number(distribution: Distribution, number: &str) -> Option<Self> {
+        let mut parts = number.split(['.', '-']);
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().and_then(|minor| minor.parse().ok()).unwrap_or(0);
+        Some(ServerVersion { distribution, number: number.to_string(), major, minor })
+    }
+
+    /// The `version` object of `GET /` with the fields read by `parse`
+    fn to_json(&self) -> Value {
+        match self.distribution {
+            Distribution::OpenSearch => json!({ "number": self.number, "distribution": "opensearch" }),
+            Distribution::Elasticsearch => json!({ "number": self.number })
+        }
+    }
+
+    /// Elasticsearch 7 counts up to 10,000 hits by default, the older versions reject the parameter
+    pub fn supports_track_total_hits(&self) -> bool {
+        self.distribution == Distribution::OpenSearch || self.major >= 7
+    }
+}
+
+/// Versions of the servers detected by the previous runs, stored in the JSON file next to the configuration file.
+/// The version is kept with the address of the server, it is requested again when the address is changed.
+pub struct VersionCache {
+    path: PathBuf,
+    server: String,
+    address: String
+}
+
+impl VersionCache {
+    pub fn new(path: PathBuf, server: &ElasticSearchServer) -> Self {
+        VersionCache {
+            path,
+            server: server.name.clone(),
+            address: server.cloud_id.clone().unwrap_or_else(|| server.server.clone())
+        }
+    }
+
+    pub fn load(&self) -> Option<ServerVersion> {
+        let versions = self.read();
+        let entry = versions.get(&self.server)?;
+        if entry.get("address").and_then(Value::as_str) != Some(self.address.as_str()) {
+            return None;
+        }
+        ServerVersion::parse(entry)
+    }
+
+    /// The cache is optional, the version is requested again if it cannot be saved
+    pub fn store(&self, version: &ServerVersion) {
+        let mut versions = self.read();
+        versions.insert(self.server.clone(), json!({ "address": self.address, "version": version.to_json() }));
+        let content = serde_json::to_string_pretty(&versions).unwrap_or_default();
+        if let Err(err) = fs::write(&self.path, content) {
+            warn!("Cannot save the version of the server to {}: {}", self.path.display(), err);
+        }
+    }
+
+    fn read(&self) -> Map<String, Value> {
+        fs::read(&self.path).ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Version of the server requested once and kept for the next searches, and for the next runs with the cache
+#[derive(Default)]
+pub struct VersionProbe {
+    version: Mutex<Option<Option<ServerVersion>>>,
+    cache: Option<VersionCache>
+}
+
+impl VersionProbe {
+    /// The version is read from the cache, the detected version is stored to it
+    pub fn cached(cache: VersionCache) -> Self {
+        let version = cache.load();
+        if let Some(version) = &version {
+            debug!("Using the cached version {:?} {}", version.distribution, version.number);
+        }
+        VersionProbe { version: Mutex::new(version.map(Some)), cache: Some(cache) }
+    }
+
+    /// Forgets the version read from the cache, so it is requested again and stored to the cache.
+    /// The version given by `--server-version` is kept.
+    pub fn uncached(self) -> Self {
+        if self.cache.is_none() {
+            return self;
+        }
+        VersionProbe { version: Mutex::new(None), cache: self.cache }
+    }
+
+    /// The version given by `--server-version`, it is never requested
+    pub fn known(version: ServerVersion) -> Self {
+        VersionProbe { version: Mutex::new(Some(Some(version))), cache: None }
+    }
+
+    /// The version given or detected before, nothing is requested
+    pub fn current(&self) -> Option<ServerVersion> {
+        self.version.lock().unwrap_or_else(|err| err.into_inner()).clone().flatten()
+    }
+
+    /// Version of the server, None if the server did not tell it, e.g. the user has no permission for `GET /`.
+    /// The request should be sent without retries, the search reports the connection errors anyway.
+    pub fn get<F>(&self, request: F) -> Option<ServerVersion>
+        where F: FnOnce() -> Result<Value, FetcherError>
+    {
+        let mut cached = self.version.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(version) = cached.as_ref() {
+            return version.clone();
+        }
+        let version = request()
+            .map_err(|err| debug!("Cannot detect the version of the server: {}", err))
+            .ok()
+            .and_then(|body| ServerVersion::parse(&body));
+        if let Some(version) = &version {
+            debug!("Detected {:?} {}", version.distribution, version.number);
+            if let Some(cache) = &self.cache {
+                cache.store(version);
+            }
+        }
+        *cached = Some(version.clone());
+        version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(body: Value) -> ServerVersion {
+        ServerVersion::parse(&body).unwrap()
+    }
+
+    #[test]
+    fn it_should_detect_distribution_and_version() {
+        let elasticsearch = version(json!({ "version": { "number": "6.8.23", "build_flavor": "default" }, "tagline": "You Know, for Search" }));
+        assert_eq!(ServerVersion { distribution: Distribution::Elasticsearch, number: "6.8.23".to_string(), major: 6, minor: 8 }, elasticsearch);
+        assert!(!elasticsearch.supports_track_total_hits());
+
+        // OpenSearch may report the compatible version of Elasticsearch
+        let opensearch = version(json!({ "version": { "distribution": "opensearch", "number": "7.10.2" } }));
+        assert_eq!(Distribution::OpenSearch, opensearch.distribution);
+        assert!(opensearch.supports_track_total_hits());
+
+        assert!(version(json!({ "version": { "number": "8.0.0-SNAPSHOT" } })).supports_track_total_hits());
+    }
+
+    #[test]
+    fn it_should_ignore_unknown_responses() {
+        assert_eq!(None, ServerVersion::parse(&json!({ "status": "green" })));
+        assert_eq!(None, ServerVersion::parse(&json!({ "version": { "number": "latest" } })));
+        assert_eq!(None, ServerVersion::from_number(Distribution::Elasticsearch, "v7"));
+    }
+
+    #[test]
+    fn it_should_use_known_version_without_request() {
+        let known = ServerVersion::from_number(Distribution::Elasticsearch, "7.17.0").unwrap();
+        assert_eq!(7, known.major);
+        assert_eq!(17, known.minor);
+
+        let probe = VersionProbe::known(known.clone());
+        assert_eq!(Some(known.clone()), probe.current());
+        assert_eq!(Some(known), probe.get(|| panic!("the version should not be requested")));
+        assert_eq!(None, VersionProbe::default().current());
+    }
+
+    #[test]
+    fn it_should_keep_versions_of_servers_in_cache() {
+        let path = std::env::temp_dir().join(format!("elastic-cli-versions-{}", std::process::id()));
+        let server = |name: &str, address: &str| ElasticSearchServer { name: name.to_string(), server: address.to_string(), ..ElasticSearchServer::default() };
+        let opensearch = version(json!({ "version": { "distribution": "opensearch", "number": "2.11.0" } }));
+        let elasticsearch = version(json!({ "version": { "number": "6.8.23" } }));
+
+        VersionCache::new(path.clone(), &server("logs", "http://logs:9200")).store(&opensearch);
+        VersionCache::new(path.clone(), &server("old", "http://old:9200")).store(&elasticsearch);
+        assert_eq!(Some(opensearch), VersionCache::new(path.clone(), &server("logs", "http://logs:9200")).load());
+        assert_eq!(Some(elasticsearch), VersionCache::new(path.clone(), &server("old", "http://old:9200")).load());
+        assert_eq!(None, VersionCache::new(path.clone(), &server("logs", "http://moved:9200")).load());
+
+        // the cached version is used without the request
+        let probe = VersionProbe::cached(VersionCache::new(path.clone(), &server("old", "http://old:9200")));
+        assert_eq!(Some(6), probe.get(|| panic!("the version should not be requested")).map(|version| version.major));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -9,10 +9,11 @@ pub use self::password_questioner::*;
 
 use clap::{ArgMatches};
 use commands::{Command};
-use config::{ApplicationConfig, ElasticSearchServerType, SecretsWriter};
+use config::{ApplicationConfig, AuthType, AwsSigV4Config, ElasticSearchServerType, SecretsWriter, TlsConfig, DEFAULT_AWS_SERVICE};
 use serde_yaml;
 use error::ApplicationError;
 
+use std::env;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -30,7 +31,11 @@ pub enum ConfigAction {
         server_type: ElasticSearchServerType,
         index: Option<String>,
         username: Option<String>,
-        password: Option<String>
+        password: Option<String>,
+        auth_type: Option<AuthType>,
+        token: Option<String>,
+        tls: TlsOptions,
+        connection: ConnectionOptions
     },
     UpdateServer {
         name: String,
@@ -39,12 +44,136 @@ pub enum ConfigAction {
         index: Option<String>,
         username: Option<String>,
         password: Option<String>,
-        ask_password: bool
+        ask_password: bool,
+        auth_type: Option<AuthType>,
+        token: Option<String>,
+        tls: TlsOptions,
+        connection: ConnectionOptions
     },
     UseServer { name: String },
     Show
 }
 
+/// TLS settings passed to the server commands, empty paths remove the settings
+#[derive(Clone, Default)]
+pub struct TlsOptions {
+    pub ca_cert: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    pub client_cert_password: Option<String>,
+    pub insecure_skip_verify: Option<bool>
+}
+
+impl TlsOptions {
+    fn parse(args: &ArgMatches) -> Self {
+        let insecure_skip_verify = if args.is_present("insecure-skip-verify") {
+            Some(true)
+        } else if args.is_present("verify-certificates") {
+            Some(false)
+        } else {
+            None
+        };
+        TlsOptions {
+            ca_cert: args.value_of("ca-cert").map(str::to_owned),
+            client_cert: args.value_of("client-cert").map(str::to_owned),
+            client_key: args.value_of("client-key").map(str::to_owned),
+            client_cert_password: args.value_of("client-cert-password").map(str::to_owned),
+            insecure_skip_verify
+        }
+    }
+
+    pub fn apply(&self, tls: &mut TlsConfig) {
+        let path = |path: &String| if path.is_empty() { None } else { Some(path.clone()) };
+        if let Some(ca_cert) = &self.ca_cert {
+            tls.ca_cert = path(ca_cert);
+        }
+        if let Some(client_cert) = &self.client_cert {
+            tls.client_cert = path(client_cert);
+        }
+        if let Some(client_key) = &self.client_key {
+            tls.client_key = path(client_key);
+        }
+        if let Some(insecure_skip_verify) = self.insecure_skip_verify {
+            tls.insecure_skip_verify = insecure_skip_verify;
+        }
+    }
+
+    /// The certificate files or the password are changed
+    pub fn changes_identity(&self) -> bool {
+        self.client_cert.is_some() || self.client_key.is_some() || self.client_cert_password.is_some()
+    }
+}
+
+/// Proxy, headers, timeouts and retries passed to the server commands, empty values remove the proxy settings
+#[derive(Clone, Default)]
+pub struct ConnectionOptions {
+    pub proxy: Option<String>,
+    pub no_proxy: Option<Vec<String>>,
+    pub headers: Vec<(String, String)>,
+    /// Headers with the values kept in the keyring, the value is asked if it is not passed
+    pub secret_headers: Vec<(String, Option<String>)>,
+    pub remove_headers: Vec<String>,
+    /// Cloud ID of the Elastic Cloud deployment, an empty value removes it
+    pub cloud_id: Option<String>,
+    /// Tenant of the security plugin, an empty value removes it
+    pub tenant: Option<String>,
+    /// Seconds
+    pub connect_timeout: Option<u64>,
+    /// Seconds
+    pub read_timeout: Option<u64>,
+    pub max_retries: Option<u32>
+}
+
+impl ConnectionOptions {
+    fn parse(args: &ArgMatches) -> Result<Self, ApplicationError> {
+        let values = |name: &str| args.values_of(name)
+            .map(|values| values.map(str::to_owned).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let headers = values("header").iter()
+            .map(|header| match Self::split_header(header) {
+                (name, Some(value)) => Ok((name, value)),
+                (_, None) => {
+                    error!("Header should be passed as 'Name: value' - {}", header);
+                    Err(ApplicationError)
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ConnectionOptions {
+            proxy: args.value_of("proxy").map(str::to_owned),
+            no_proxy: args.value_of("no-proxy").map(|hosts| {
+                hosts.split(',').map(str::trim).filter(|host| !host.is_empty()).map(str::to_owned).collect()
+            }),
+            headers,
+            secret_headers: values("secret-header").iter().map(|header| Self::split_header(header)).collect(),
+            remove_headers: values("remove-header"),
+            cloud_id: args.value_of("cloud-id").map(str::to_owned),
+            tenant: args.value_of("tenant").map(str::to_owned),
+            connect_timeout: Self::parse_number(args, "connect-timeout")?,
+            read_timeout: Self::parse_number(args, "read-timeout")?,
+            max_retries: Self::parse_number(args, "max-retries")?
+        })
+    }
+
+    fn parse_number<T>(args: &ArgMatches, name: &str) -> Result<Option<T>, ApplicationError>
+        where T: FromStr, T::Err: std::fmt::Display
+    {
+        args.value_of(name)
+            .map(str::parse)
+            .map_or(Ok(None), |v| v.map(Some))
+            .map_err(|err| {
+                error!("Argument '{}' has invalid value: {}", name, err);
+                ApplicationError
+            })
+    }
+
+    fn split_header(header: &str) -> (String, Option<String>) {
+        match header.find(':') {
+            Some(position) => (header[..position].trim().to_owned(), Some(header[position + 1..].trim().to_owned())),
+            None => (header.trim().to_owned(), None)
+        }
+    }
+}
+
 impl ConfigCommand {
     pub fn new(config: ApplicationConfig, secrets: Arc<SecretsWriter>, action: ConfigAction) -> Self {
         ConfigCommand {
@@ -66,10 +195,15 @@ impl ConfigCommand {
                             error!("Argument 'name' is required");
                             ApplicationError
                         })?;
-                        let address = server_match.value_of("address").ok_or_else(|| {
-                            error!("Argument 'address' is required");
-                            ApplicationError
-                        })?;
+                        // the address of the cloud deployment is decoded from the cloud ID
+                        let address = match (server_match.value_of("address"), server_match.value_of("cloud-id")) {
+                            (Some(address), _) => address,
+                            (None, Some(_)) => "",
+                            (None, None) => {
+                                error!("Argument 'address' or 'cloud-id' is required");
+                                return Err(ApplicationError);
+                            }
+                        };
                         let index = server_match.value_of("index");
                         let username = server_match.value_of("username");
                         let password = server_match.value_of("password");
@@ -80,6 +214,10 @@ impl ConfigCommand {
                                 error!("{}", err);
                                 ApplicationError
                             })?;
+                        let auth_type = Self::parse_auth_type(server_match)?;
+                        let token = server_match.value_of("token");
+                        let tls = TlsOptions::parse(server_match);
+                        let connection = ConnectionOptions::parse(server_match)?;
                         Ok(ConfigAction::AddServer {
                             name: name.to_owned(),
                             address: address.to_owned(),
@@ -87,6 +225,10 @@ impl ConfigCommand {
                             index: index.map(str::to_owned),
                             username: username.map(str::to_owned),
                             password: password.map(str::to_owned),
+                            auth_type,
+                            token: token.map(str::to_owned),
+                            tls,
+                            connection
                         })
                     },
                     (resource, _) => {
@@ -114,6 +256,10 @@ impl ConfigCommand {
                                 error!("{}", err);
                                 ApplicationError
                             })?;
+                        let auth_type = Self::parse_auth_type(server_match)?;
+                        let token = server_match.value_of("token");
+                        let tls = TlsOptions::parse(server_match);
+                        let connection = ConnectionOptions::parse(server_match)?;
 
                         Ok(ConfigAction::UpdateServer {
                             name: name.to_owned(),
@@ -122,7 +268,11 @@ impl ConfigCommand {
                             index: index.map(str::to_owned),
                             username: username.map(str::to_owned),
                             password: password.map(str::to_owned),
-                            ask_password
+                            ask_password,
+                            auth_type,
+                            token: token.map(str::to_owned),
+                            tls,
+                            connection
                         })
                     },
                     (resource, _) => {
@@ -154,6 +304,30 @@ impl ConfigCommand {
         }?;
         Ok(ConfigCommand::new(config, secrets, action))
     }
+
+    fn parse_auth_type(args: &ArgMatches) -> Result<Option<AuthType>, ApplicationError> {
+        if args.value_of("auth-type") == Some("aws_sigv4") {
+            let region = args.value_of("aws-region").map(str::to_owned)
+                .or_else(|| env::var("AWS_REGION").ok())
+                .or_else(|| env::var("AWS_DEFAULT_REGION").ok())
+                .ok_or_else(|| {
+                    error!("AWS region should be specified, use --aws-region");
+                    ApplicationError
+                })?;
+            return Ok(Some(AuthType::AwsSigV4(AwsSigV4Config {
+                region,
+                service: args.value_of("aws-service").unwrap_or(DEFAULT_AWS_SERVICE).to_owned(),
+                profile: args.value_of("aws-profile").map(str::to_owned)
+            })));
+        }
+        args.value_of("auth-type")
+            .map(AuthType::from_str)
+            .map_or(Ok(None), |v| v.map(Some))
+            .map_err(|err| {
+                error!("{}", err);
+                ApplicationError
+            })
+    }
 }
 
 impl Command for ConfigCommand {
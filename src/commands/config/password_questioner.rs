@@ -8,6 +8,8 @@ pub struct PasswordQuestionerError {
 
 pub trait PasswordQuestioner {
     fn ask_password(&self, username: &str) -> Result<String, PasswordQuestionerError>;
+
+    fn ask_secret(&self, name: &str) -> Result<String, PasswordQuestionerError>;
 }
 
 pub struct TtyPasswordQuestioner {
@@ -27,4 +29,11 @@ impl PasswordQuestioner for TtyPasswordQuestioner {
                 PasswordQuestionerError { inner: format!("{}", err) }
             })
     }
+
+    fn ask_secret(&self, name: &str) -> Result<String, PasswordQuestionerError> {
+        read_password_from_tty(Some(&format!("Enter the {}: ", name)))
+            .map_err(|err| {
+                PasswordQuestionerError { inner: format!("{}", err) }
+            })
+    }
 }
\ No newline at end of file
@@ -1,9 +1,15 @@
 use super::{
-    ConfigAction, ApplicationConfig, PasswordQuestioner
+    ConfigAction, ApplicationConfig, ConnectionOptions, PasswordQuestioner, TlsOptions
 };
 
-use crate::config::{ElasticSearchServer, SecretsWriter, WriteSecretError};
+use crate::client::{AwsCredentials, TlsError, configure_tls};
+use crate::config::{
+    AuthType, CloudId, CloudIdError, ElasticSearchServer, ElasticSearchServerType, HeaderValueSource, SecretsWriter,
+    TlsConfig, WriteSecretError
+};
+use reqwest::header::{HeaderName, HeaderValue};
 
+use std::str::FromStr;
 use std::sync::Arc;
 use commands::config::password_questioner::PasswordQuestionerError;
 
@@ -22,6 +28,18 @@ pub enum ConfigActionError {
     UsernameShouldBeSpecified,
     #[fail(display = "cannot save password: {}", inner)]
     CannotSavePassword { inner: WriteSecretError },
+    #[fail(display = "cannot save token: {}", inner)]
+    CannotSaveToken { inner: WriteSecretError },
+    #[fail(display = "AWS credentials should be passed as 'access_key_id:secret_access_key[:session_token]'")]
+    InvalidAwsCredentials,
+    #[fail(display = "invalid TLS settings: {}", inner)]
+    InvalidTlsSettings { inner: TlsError },
+    #[fail(display = "invalid cloud ID: {}", inner)]
+    InvalidCloudId { inner: CloudIdError },
+    #[fail(display = "invalid header {}", name)]
+    InvalidHeader { name: String },
+    #[fail(display = "cannot save the value of the header: {}", inner)]
+    CannotSaveHeader { inner: WriteSecretError },
     #[fail(display = "{}", inner)]
     CannotRetrievePassword { inner: PasswordQuestionerError },
 }
@@ -45,7 +63,11 @@ impl ConfigActionResolver {
                 server_type,
                 index,
                 username,
-                password
+                password,
+                auth_type,
+                token,
+                tls,
+                connection
             } => {
                 if config.servers.iter().any(|server| server.name == name) {
                     return Err(ConfigActionError::ServerAlreadyExists { server_name: name })
@@ -53,15 +75,31 @@ impl ConfigActionResolver {
                 if config.default_server.is_none() {
                     config.default_server = Some(name.clone());
                 }
-                config.servers.push(ElasticSearchServer {
+                let mut server = ElasticSearchServer {
                     name,
                     server: address,
                     server_type,
                     default_index: index,
-                    username: username.clone()
-                });
+                    username: username.clone(),
+                    auth: auth_type,
+                    ..ElasticSearchServer::default()
+                };
+                tls.apply(&mut server.tls);
+                self.save_tls(&server, &tls)?;
+                self.save_connection(&mut server, connection)?;
+                let effective_auth_type = server.auth_type();
+                let token_key = server.token_key();
+                config.servers.push(server);
 
-                let password_needed = username.is_some();
+                if let AuthType::AwsSigV4(_) = effective_auth_type {
+                    self.save_aws_credentials(&token_key, token)?;
+                    return Ok(config);
+                }
+                if let AuthType::ApiKey | AuthType::Bearer = effective_auth_type {
+                    self.save_token(&token_key, effective_auth_type, token, true)?;
+                    return Ok(config);
+                }
+                let password_needed = effective_auth_type == AuthType::Basic;
                 if let Some((username, password)) = self.fetch_credentials(username, password, password_needed)? {
                     info!("Saving password to the system keychain...");
                     self.secrets_writer.write(&username, &password)
@@ -77,7 +115,11 @@ impl ConfigActionResolver {
                 index,
                 username,
                 password,
-                ask_password
+                ask_password,
+                auth_type,
+                token,
+                tls,
+                connection
             } => {
                 let mut server = config.servers.iter_mut().find(|server| server.name == name)
                     .ok_or_else(|| {
@@ -96,8 +138,24 @@ impl ConfigActionResolver {
                 if username.is_some() {
                     server.username = username.clone();
                 }
+                if auth_type.is_some() {
+                    server.auth = auth_type.clone();
+                }
+                tls.apply(&mut server.tls);
+                self.save_tls(server, &tls)?;
+                self.save_connection(server, connection)?;
 
-                let password_needed = username.is_some() || ask_password;
+                let effective_auth_type = server.auth_type();
+                if let AuthType::AwsSigV4(_) = effective_auth_type {
+                    self.save_aws_credentials(&server.token_key(), token)?;
+                    return Ok(config);
+                }
+                if let AuthType::ApiKey | AuthType::Bearer = effective_auth_type {
+                    let ask_token = auth_type.is_some() || ask_password;
+                    self.save_token(&server.token_key(), effective_auth_type, token, ask_token)?;
+                    return Ok(config);
+                }
+                let password_needed = username.is_some() || ask_password || auth_type == Some(AuthType::Basic);
                 if let Some((username, password)) = self.fetch_credentials(server.username.clone(), password, password_needed)? {
                     info!("Saving password to the system keychain...");
                     self.secrets_writer.write(&username, &password)
@@ -118,6 +176,141 @@ impl ConfigActionResolver {
         Ok(config)
     }
 
+    /// Checks the changed certificate files and saves the password of the client certificate
+    fn save_tls(&self, server: &ElasticSearchServer, options: &TlsOptions) -> Result<(), ConfigActionError> {
+        if options.insecure_skip_verify == Some(true) {
+            warn!("TLS certificate verification is DISABLED for the server {}, the connection is NOT secure", server.name);
+        }
+        // the stored password is not available here, so the client certificate is checked only if it is changed
+        let changed = TlsConfig {
+            ca_cert: options.ca_cert.as_ref().and(server.tls.ca_cert.clone()),
+            client_cert: if options.changes_identity() { server.tls.client_cert.clone() } else { None },
+            client_key: if options.changes_identity() { server.tls.client_key.clone() } else { None },
+            insecure_skip_verify: false
+        };
+        let password = options.client_cert_password.as_deref().unwrap_or("");
+        configure_tls(reqwest::Client::builder(), &changed, password)
+            .map_err(|err| ConfigActionError::InvalidTlsSettings { inner: err })?;
+
+        if let Some(password) = &options.client_cert_password {
+            info!("Saving the password of the client certificate to the system keychain...");
+            self.secrets_writer.write(&server.client_cert_password_key(), password)
+                .map_err(|err| {
+                    ConfigActionError::CannotSavePassword { inner: err }
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Applies the proxy, the headers and the request settings, the secret values of the headers are saved to the keyring
+    fn save_connection(&self, server: &mut ElasticSearchServer, options: ConnectionOptions) -> Result<(), ConfigActionError> {
+        if let Some(proxy) = options.proxy {
+            server.proxy = if proxy.is_empty() { None } else { Some(proxy) };
+        }
+        if let Some(no_proxy) = options.no_proxy {
+            server.no_proxy = no_proxy;
+        }
+        if let Some(cloud_id) = options.cloud_id {
+            if !cloud_id.is_empty() {
+                let decoded = CloudId::from_str(&cloud_id)
+                    .map_err(|err| ConfigActionError::InvalidCloudId { inner: err })?;
+                if server.server_type == ElasticSearchServerType::Kibana && decoded.kibana_url.is_none() {
+                    return Err(ConfigActionError::InvalidCloudId { inner: CloudIdError::NoKibana });
+                }
+            }
+            server.cloud_id = if cloud_id.is_empty() { None } else { Some(cloud_id) };
+        }
+        if let Some(tenant) = options.tenant {
+            server.tenant = if tenant.is_empty() { None } else { Some(tenant) };
+        }
+        if options.connect_timeout.is_some() {
+            server.request.connect_timeout = options.connect_timeout;
+        }
+        if options.read_timeout.is_some() {
+            server.request.read_timeout = options.read_timeout;
+        }
+        if options.max_retries.is_some() {
+            server.request.max_retries = options.max_retries;
+        }
+        for name in &options.remove_headers {
+            if server.headers.remove(name).is_none() {
+                warn!("Header {} is not set for the server {}", name, server.name);
+            }
+        }
+        for (name, value) in options.headers {
+            Self::check_header(&name, &value)?;
+            server.headers.insert(name, HeaderValueSource::Plain(value));
+        }
+        for (name, value) in options.secret_headers {
+            let value = match value {
+                Some(value) => value,
+                None => self.password_questioner.ask_secret(&format!("value of the header {}", name))
+                    .map_err(|err| {
+                        ConfigActionError::CannotRetrievePassword { inner: err }
+                    })?
+            };
+            Self::check_header(&name, &value)?;
+            let key = server.header_key(&name);
+            info!("Saving the value of the header {} to the system keychain...", name);
+            self.secrets_writer.write(&key, &value)
+                .map_err(|err| {
+                    ConfigActionError::CannotSaveHeader { inner: err }
+                })?;
+            server.headers.insert(name, HeaderValueSource::Keyring { keyring: key });
+        }
+        Ok(())
+    }
+
+    fn check_header(name: &str, value: &str) -> Result<(), ConfigActionError> {
+        HeaderName::from_bytes(name.as_bytes()).ok()
+            .and(HeaderValue::from_str(value).ok())
+            .map(|_| ())
+            .ok_or_else(|| ConfigActionError::InvalidHeader { name: name.to_string() })
+    }
+
+    /// Saves the API key or the bearer token, it is asked if not passed
+    fn save_token(
+        &self,
+        key: &str,
+        auth_type: AuthType,
+        token: Option<String>,
+        ask_token: bool
+    ) -> Result<(), ConfigActionError> {
+        let token = match (token, ask_token) {
+            (Some(token), _) => token,
+            (None, true) => {
+                let name = if auth_type == AuthType::ApiKey { "API key" } else { "bearer token" };
+                self.password_questioner.ask_secret(name)
+                    .map_err(|err| {
+                        ConfigActionError::CannotRetrievePassword { inner: err }
+                    })?
+            },
+            (None, false) => return Ok(())
+        };
+        info!("Saving token to the system keychain...");
+        self.secrets_writer.write(key, &token)
+            .map_err(|err| {
+                ConfigActionError::CannotSaveToken { inner: err }
+            })
+    }
+
+    /// Saves the access key, otherwise the credentials are read from the environment or the profile files
+    fn save_aws_credentials(&self, key: &str, credentials: Option<String>) -> Result<(), ConfigActionError> {
+        let credentials = match credentials {
+            Some(credentials) => credentials,
+            None => {
+                info!("AWS credentials will be read from the environment variables or the profile files");
+                return Ok(())
+            }
+        };
+        AwsCredentials::parse(&credentials).ok_or(ConfigActionError::InvalidAwsCredentials)?;
+        info!("Saving AWS credentials to the system keychain...");
+        self.secrets_writer.write(key, &credentials)
+            .map_err(|err| {
+                ConfigActionError::CannotSaveToken { inner: err }
+            })
+    }
+
     fn fetch_credentials(
         &self,
         username: Option<String>,
@@ -1,9 +1,10 @@
 use super::{create_config, create_resolver};
 
-use config::{ElasticSearchServer, ElasticSearchServerType};
+use config::{AuthType, AwsSigV4Config, CloudIdError, ElasticSearchServer, ElasticSearchServerType, HeaderValueSource, TlsConfig};
 use commands::config::resolver::{ConfigActionError};
 
 use commands::ConfigAction;
+use commands::config::{ConnectionOptions, TlsOptions};
 
 #[test]
 fn should_not_creates_2_servers_with_same_name() {
@@ -12,10 +13,7 @@ fn should_not_creates_2_servers_with_same_name() {
 
     config.servers.push(ElasticSearchServer {
         name: "test".to_string(),
-        server: "".to_string(),
-        server_type: ElasticSearchServerType::Elastic,
-        default_index: None,
-        username: None
+        ..ElasticSearchServer::default()
     });
 
     let result = resolver.resolve(ConfigAction::AddServer {
@@ -24,7 +22,11 @@ fn should_not_creates_2_servers_with_same_name() {
         server_type: ElasticSearchServerType::Elastic,
         index: None,
         username: None,
-        password: None
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config);
 
     assert_eq!(Err(ConfigActionError::ServerAlreadyExists { server_name: "test".to_string() }), result);
@@ -41,7 +43,11 @@ fn should_sets_default_server_if_its_not_set() {
         server_type: ElasticSearchServerType::Elastic,
         index: None,
         username: None,
-        password: None
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config).unwrap();
 
     assert_eq!(Some("test".to_string()), new_config.default_server);
@@ -58,7 +64,11 @@ fn should_puts_new_server() {
         server_type: ElasticSearchServerType::Kibana,
         index: Some("index".to_string()),
         username: Some("username".to_string()),
-        password: None
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config).unwrap();
 
     assert_eq!(vec![
@@ -67,7 +77,8 @@ fn should_puts_new_server() {
             server: "address".to_string(),
             server_type: ElasticSearchServerType::Kibana,
             default_index: Some("index".to_string()),
-            username: Some("username".to_string())
+            username: Some("username".to_string()),
+            ..ElasticSearchServer::default()
         }
     ], new_config.servers);
 }
@@ -83,7 +94,11 @@ fn should_asks_for_password_if_username_is_present() {
         server_type: ElasticSearchServerType::Kibana,
         index: Some("index".to_string()),
         username: Some("username".to_string()),
-        password: None
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config).unwrap();
 
     assert_eq!(true, password.was_asked());
@@ -101,7 +116,11 @@ fn should_not_asks_for_password_if_username_is_not_present() {
         server_type: ElasticSearchServerType::Kibana,
         index: Some("index".to_string()),
         username: None,
-        password: None
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config).unwrap();
 
     assert_eq!(false, password.was_asked());
@@ -119,7 +138,11 @@ fn should_not_asks_for_password_if_password_is_present() {
         server_type: ElasticSearchServerType::Kibana,
         index: Some("index".to_string()),
         username: Some("username".to_string()),
-        password: Some("password".to_string())
+        password: Some("password".to_string()),
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config).unwrap();
 
     assert_eq!(false, password.was_asked());
@@ -137,8 +160,272 @@ fn should_fails_when_password_is_specified_but_username_is_not() {
         server_type: ElasticSearchServerType::Kibana,
         index: Some("index".to_string()),
         username: None,
-        password: Some("password".to_string())
+        password: Some("password".to_string()),
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config);
 
     assert_eq!(Err(ConfigActionError::UsernameShouldBeSpecified), result);
-}
\ No newline at end of file
+}
+#[test]
+fn should_save_token_by_server_name() {
+    let config = create_config();
+    let (resolver, password, secrets) = create_resolver();
+
+    let new_config = resolver.resolve(ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: Some(AuthType::ApiKey),
+        token: Some("id:key".to_string()),
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
+    }, config).unwrap();
+
+    assert_eq!(Some(AuthType::ApiKey), new_config.servers[0].auth);
+    assert!(!password.was_asked());
+    secrets.assert_key("test/api_key");
+    secrets.assert_check("id:key".to_string());
+}
+
+#[test]
+fn should_asks_for_token_if_it_is_not_present() {
+    let config = create_config();
+    let (resolver, password, secrets) = create_resolver();
+
+    resolver.resolve(ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: Some("username".to_string()),
+        password: None,
+        auth_type: Some(AuthType::Bearer),
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
+    }, config).unwrap();
+
+    assert!(password.was_asked());
+    secrets.assert_key("test/bearer");
+    secrets.assert_check("asked_token".to_string());
+}
+
+#[test]
+fn should_fails_when_basic_auth_is_specified_without_username() {
+    let config = create_config();
+    let (resolver, _password, _secrets) = create_resolver();
+
+    let result = resolver.resolve(ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: Some(AuthType::Basic),
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
+    }, config);
+
+    assert_eq!(Err(ConfigActionError::UsernameShouldBeSpecified), result);
+}
+
+#[test]
+fn should_fails_when_tls_files_are_invalid() {
+    let config = create_config();
+    let (resolver, _password, _secrets) = create_resolver();
+
+    let result = resolver.resolve(ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions {
+            ca_cert: Some("/nonexistent/ca.pem".to_string()),
+            ..TlsOptions::default()
+        },
+        connection: ConnectionOptions::default()
+    }, config);
+
+    match result {
+        Err(ConfigActionError::InvalidTlsSettings { .. }) => {},
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn should_puts_insecure_tls_settings() {
+    let config = create_config();
+    let (resolver, _password, _secrets) = create_resolver();
+
+    let new_config = resolver.resolve(ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions {
+            insecure_skip_verify: Some(true),
+            ..TlsOptions::default()
+        },
+        connection: ConnectionOptions::default()
+    }, config).unwrap();
+
+    assert_eq!(TlsConfig { insecure_skip_verify: true, ..TlsConfig::default() }, new_config.servers[0].tls);
+}
+
+#[test]
+fn should_puts_proxy_and_headers() {
+    let config = create_config();
+    let (resolver, password, secrets) = create_resolver();
+
+    let new_config = resolver.resolve(ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions {
+            proxy: Some("http://proxy:3128".to_string()),
+            no_proxy: Some(vec!["localhost".to_string()]),
+            headers: vec![("X-Tenant".to_string(), "team".to_string())],
+            secret_headers: vec![("X-Token".to_string(), None)],
+            remove_headers: vec![],
+            ..ConnectionOptions::default()
+        }
+    }, config).unwrap();
+
+    let server = &new_config.servers[0];
+    assert_eq!(Some("http://proxy:3128".to_string()), server.proxy);
+    assert_eq!(vec!["localhost".to_string()], server.no_proxy);
+    assert_eq!(Some(&HeaderValueSource::Plain("team".to_string())), server.headers.get("X-Tenant"));
+    assert_eq!(Some(&HeaderValueSource::Keyring { keyring: "test/headers/X-Token".to_string() }), server.headers.get("X-Token"));
+    assert!(password.was_asked());
+    secrets.assert_key("test/headers/X-Token");
+    secrets.assert_check("asked_token".to_string());
+}
+
+#[test]
+fn should_fails_when_header_is_invalid() {
+    let config = create_config();
+    let (resolver, _password, _secrets) = create_resolver();
+
+    let result = resolver.resolve(ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions {
+            headers: vec![("X Tenant".to_string(), "team".to_string())],
+            ..ConnectionOptions::default()
+        }
+    }, config);
+
+    assert_eq!(Err(ConfigActionError::InvalidHeader { name: "X Tenant".to_string() }), result);
+}
+
+#[test]
+fn should_puts_aws_auth_without_asking_credentials() {
+    let config = create_config();
+    let (resolver, password, _secrets) = create_resolver();
+    let aws = AuthType::AwsSigV4(AwsSigV4Config { region: "eu-west-1".to_string(), service: "es".to_string(), profile: None });
+
+    let new_config = resolver.resolve(ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: Some(aws.clone()),
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
+    }, config).unwrap();
+
+    assert_eq!(Some(aws), new_config.servers[0].auth);
+    assert!(!password.was_asked());
+}
+
+#[test]
+fn should_saves_aws_credentials() {
+    let config = create_config();
+    let (resolver, _password, secrets) = create_resolver();
+    let aws = AuthType::AwsSigV4(AwsSigV4Config { region: "eu-west-1".to_string(), service: "es".to_string(), profile: None });
+    let action = |token: &str| ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "address".to_string(),
+        server_type: ElasticSearchServerType::Elastic,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: Some(aws.clone()),
+        token: Some(token.to_string()),
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
+    };
+
+    resolver.resolve(action("AKID:secret"), config.clone()).unwrap();
+    secrets.assert_key("test/aws_sigv4");
+    secrets.assert_check("AKID:secret".to_string());
+
+    assert_eq!(Err(ConfigActionError::InvalidAwsCredentials), resolver.resolve(action("AKID"), config));
+}
+
+#[test]
+fn should_puts_cloud_id() {
+    let config = create_config();
+    let (resolver, _password, _secrets) = create_resolver();
+    // logs:base64("eu-west-1.aws.found.io$es-id$kibana-id")
+    let cloud_id = "logs:ZXUtd2VzdC0xLmF3cy5mb3VuZC5pbyRlcy1pZCRraWJhbmEtaWQ=";
+    let action = |server_type: ElasticSearchServerType, cloud_id: &str| ConfigAction::AddServer {
+        name: "test".to_string(),
+        address: "".to_string(),
+        server_type,
+        index: None,
+        username: None,
+        password: None,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions { cloud_id: Some(cloud_id.to_string()), ..ConnectionOptions::default() }
+    };
+
+    let new_config = resolver.resolve(action(ElasticSearchServerType::Kibana, cloud_id), config.clone()).unwrap();
+    let server = &new_config.servers[0];
+    assert_eq!(Some(cloud_id.to_string()), server.cloud_id);
+    assert_eq!(Ok("https://kibana-id.eu-west-1.aws.found.io".to_string()), server.address());
+
+    assert_eq!(
+        Err(ConfigActionError::InvalidCloudId { inner: CloudIdError::MissingSeparator }),
+        resolver.resolve(action(ElasticSearchServerType::Elastic, "ZXUtd2VzdC0x"), config.clone())
+    );
+    // logs:base64("eu-west-1.aws.found.io$es-id")
+    assert_eq!(
+        Err(ConfigActionError::InvalidCloudId { inner: CloudIdError::NoKibana }),
+        resolver.resolve(action(ElasticSearchServerType::Kibana, "logs:ZXUtd2VzdC0xLmF3cy5mb3VuZC5pbyRlcy1pZA=="), config)
+    );
+}
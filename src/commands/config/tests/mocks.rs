@@ -29,17 +29,25 @@ impl PasswordQuestioner for TestPasswordQuestioner {
         self.is_called.set(true);
         Ok("asked_password".to_string())
     }
+
+    fn ask_secret(&self, _name: &str) -> Result<String, PasswordQuestionerError> {
+        self.is_called.set(true);
+        Ok("asked_token".to_string())
+    }
 }
 
 pub struct TestSecrets{
+    key: RefCell<String>,
     password: RefCell<String>
 }
 impl TestSecrets {
-    pub fn new() -> Self { Self { password: RefCell::new("".to_string()) } }
+    pub fn new() -> Self { Self { key: RefCell::new("".to_string()), password: RefCell::new("".to_string()) } }
     pub fn assert_check(&self, pwd: String) { assert_eq!(pwd, *self.password.borrow()); }
+    pub fn assert_key(&self, key: &str) { assert_eq!(key, *self.key.borrow()); }
 }
 impl SecretsWriter for TestSecrets {
-    fn write(&self, _key: &str, secret: &str) -> Result<(), WriteSecretError> {
+    fn write(&self, key: &str, secret: &str) -> Result<(), WriteSecretError> {
+        self.key.replace(key.to_string());
         self.password.replace(secret.to_string());
         Ok(())
     }
@@ -10,13 +10,14 @@ pub use self::update_server_tests::*;
 pub use self::use_server_tests::*;
 pub use self::show_tests::*;
 
-use config::{ApplicationConfig, ElasticSearchServer, ElasticSearchServerType};
+use config::{ApplicationConfig, ElasticSearchServer, PagerConfig};
 
 fn create_config() -> ApplicationConfig {
     ApplicationConfig {
         file_path: "".to_string(),
         default_server: None,
-        servers: vec![ ]
+        servers: vec![ ],
+        pager: PagerConfig::default()
     }
 }
 
@@ -28,10 +29,9 @@ fn create_config_with_one_server() -> ApplicationConfig {
             ElasticSearchServer {
                 name: "test".to_string(),
                 server: "address".to_string(),
-                server_type: ElasticSearchServerType::Elastic,
-                default_index: None,
-                username: None
+                ..ElasticSearchServer::default()
             }
-        ]
+        ],
+        pager: PagerConfig::default()
     }
 }
@@ -1,9 +1,10 @@
 use super::{create_config, create_config_with_one_server, create_resolver};
 
-use config::{ElasticSearchServerType};
+use config::{AuthType, ElasticSearchServerType, HeaderValueSource};
 use commands::config::resolver::{ConfigActionError};
 
 use commands::ConfigAction;
+use commands::config::{ConnectionOptions, TlsOptions};
 
 #[test]
 fn should_fails_on_updating_nonexistent_server() {
@@ -17,7 +18,11 @@ fn should_fails_on_updating_nonexistent_server() {
         index: None,
         username: None,
         password: None,
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config);
 
     assert_eq!(Err(ConfigActionError::ServerDoesNotExists { server_name: "test".to_string() }), result);
@@ -35,7 +40,11 @@ fn should_not_change_config_if_no_options_provided() {
         index: None,
         username: None,
         password: None,
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone()).unwrap();
 
     assert_eq!(config, new_config);
@@ -53,7 +62,11 @@ fn should_update_address() {
         index: None,
         username: None,
         password: None,
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone()).unwrap();
 
     config.servers.get_mut(0).unwrap().server = "updated_address".to_string();
@@ -74,7 +87,11 @@ fn should_update_server_type() {
         index: None,
         username: None,
         password: None,
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone()).unwrap();
 
     config.servers.get_mut(0).unwrap().server_type = ElasticSearchServerType::Kibana;
@@ -94,7 +111,11 @@ fn should_update_default_index() {
         index: Some("updated_index".to_string()),
         username: None,
         password: None,
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone()).unwrap();
 
     config.servers.get_mut(0).unwrap().default_index = Some("updated_index".to_string());
@@ -114,7 +135,11 @@ fn should_asks_for_password_if_username_is_present() {
         index: None,
         username: Some("updated_username".to_string()),
         password: None,
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone()).unwrap();
 
     config.servers.get_mut(0).unwrap().username = Some("updated_username".to_string());
@@ -136,7 +161,11 @@ fn should_not_ask_for_password_if_password_is_provided() {
         index: None,
         username: Some("updated_username".to_string()),
         password: Some("updated_password".to_string()),
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone()).unwrap();
 
     config.servers.get_mut(0).unwrap().username = Some("updated_username".to_string());
@@ -158,7 +187,11 @@ fn should_not_ask_for_password_if_ask_password_is_not_provided() {
         index: None,
         username: None,
         password: None,
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone()).unwrap();
 
     assert!(!password.was_asked());
@@ -176,7 +209,11 @@ fn should_fails_if_password_provided_for_config_without_username() {
         index: None,
         username: None,
         password: Some("updated_password".to_string()),
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone());
 
     assert_eq!(Err(ConfigActionError::UsernameShouldBeSpecified), result);
@@ -194,8 +231,188 @@ fn should_fails_if_password_ask_provided_for_config_without_username() {
         index: None,
         username: None,
         password: None,
-        ask_password: true
+        ask_password: true,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config.clone());
 
     assert_eq!(Err(ConfigActionError::UsernameShouldBeSpecified), result);
-}
\ No newline at end of file
+}
+#[test]
+fn should_asks_for_token_when_auth_type_is_changed() {
+    let mut config = create_config_with_one_server();
+    let (resolver, password, secrets) = create_resolver();
+
+    let new_config = resolver.resolve(ConfigAction::UpdateServer {
+        name: "test".to_string(),
+        address: None,
+        server_type: None,
+        index: None,
+        username: None,
+        password: None,
+        ask_password: false,
+        auth_type: Some(AuthType::Bearer),
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
+    }, config.clone()).unwrap();
+
+    config.servers.get_mut(0).unwrap().auth = Some(AuthType::Bearer);
+
+    assert_eq!(config, new_config);
+    assert!(password.was_asked());
+    secrets.assert_key("test/bearer");
+    secrets.assert_check("asked_token".to_string());
+}
+
+#[test]
+fn should_update_token_without_asking() {
+    let mut config = create_config_with_one_server();
+    config.servers.get_mut(0).unwrap().auth = Some(AuthType::ApiKey);
+    let (resolver, password, secrets) = create_resolver();
+
+    resolver.resolve(ConfigAction::UpdateServer {
+        name: "test".to_string(),
+        address: None,
+        server_type: None,
+        index: None,
+        username: None,
+        password: None,
+        ask_password: false,
+        auth_type: None,
+        token: Some("encoded".to_string()),
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
+    }, config).unwrap();
+
+    assert!(!password.was_asked());
+    secrets.assert_key("test/api_key");
+    secrets.assert_check("encoded".to_string());
+}
+
+#[test]
+fn should_remove_tls_settings_with_empty_values() {
+    let mut config = create_config_with_one_server();
+    config.servers.get_mut(0).unwrap().tls.client_key = Some("client.key".to_string());
+    config.servers.get_mut(0).unwrap().tls.insecure_skip_verify = true;
+    let (resolver, _password, _secrets) = create_resolver();
+
+    let new_config = resolver.resolve(ConfigAction::UpdateServer {
+        name: "test".to_string(),
+        address: None,
+        server_type: None,
+        index: None,
+        username: None,
+        password: None,
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions {
+            client_key: Some("".to_string()),
+            insecure_skip_verify: Some(false),
+            ..TlsOptions::default()
+        },
+        connection: ConnectionOptions::default()
+    }, config).unwrap();
+
+    assert!(new_config.servers[0].tls.is_empty());
+}
+
+#[test]
+fn should_remove_proxy_and_headers() {
+    let mut config = create_config_with_one_server();
+    {
+        let server = config.servers.get_mut(0).unwrap();
+        server.proxy = Some("http://proxy:3128".to_string());
+        server.no_proxy = vec!["localhost".to_string()];
+        server.headers.insert("X-Tenant".to_string(), HeaderValueSource::Plain("team".to_string()));
+    }
+    let (resolver, password, _secrets) = create_resolver();
+
+    let new_config = resolver.resolve(ConfigAction::UpdateServer {
+        name: "test".to_string(),
+        address: None,
+        server_type: None,
+        index: None,
+        username: None,
+        password: None,
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions {
+            proxy: Some("".to_string()),
+            no_proxy: Some(vec![]),
+            remove_headers: vec!["X-Tenant".to_string()],
+            secret_headers: vec![("X-Token".to_string(), Some("secret".to_string()))],
+            ..ConnectionOptions::default()
+        }
+    }, config).unwrap();
+
+    let server = &new_config.servers[0];
+    assert_eq!(None, server.proxy);
+    assert!(server.no_proxy.is_empty());
+    assert_eq!(vec!["X-Token"], server.headers.keys().collect::<Vec<_>>());
+    assert!(!password.was_asked());
+}
+
+#[test]
+fn should_update_timeouts_and_retries() {
+    let mut config = create_config_with_one_server();
+    config.servers.get_mut(0).unwrap().request.read_timeout = Some(120);
+    let (resolver, _password, _secrets) = create_resolver();
+
+    let new_config = resolver.resolve(ConfigAction::UpdateServer {
+        name: "test".to_string(),
+        address: None,
+        server_type: None,
+        index: None,
+        username: None,
+        password: None,
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions {
+            connect_timeout: Some(5),
+            max_retries: Some(0),
+            ..ConnectionOptions::default()
+        }
+    }, config).unwrap();
+
+    let request = &new_config.servers[0].request;
+    assert_eq!(Some(5), request.connect_timeout);
+    assert_eq!(Some(120), request.read_timeout);
+    assert_eq!(Some(0), request.max_retries);
+}
+
+#[test]
+fn should_update_server_type_and_tenant() {
+    let config = create_config_with_one_server();
+    let (resolver, _password, _secrets) = create_resolver();
+    let action = |tenant: &str| ConfigAction::UpdateServer {
+        name: "test".to_string(),
+        address: None,
+        server_type: Some(ElasticSearchServerType::OpenSearchDashboards),
+        index: None,
+        username: None,
+        password: None,
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions {
+            tenant: Some(tenant.to_string()),
+            ..ConnectionOptions::default()
+        }
+    };
+
+    let new_config = resolver.resolve(action("global"), config).unwrap();
+    assert_eq!(ElasticSearchServerType::OpenSearchDashboards, new_config.servers[0].server_type);
+    assert_eq!(Some("global".to_string()), new_config.servers[0].tenant);
+
+    let new_config = resolver.resolve(action(""), new_config).unwrap();
+    assert_eq!(None, new_config.servers[0].tenant);
+}
@@ -3,6 +3,7 @@ use super::{create_config, create_config_with_one_server, create_resolver};
 use commands::config::resolver::{ConfigActionError};
 
 use commands::ConfigAction;
+use commands::config::{ConnectionOptions, TlsOptions};
 
 #[test]
 fn should_fails_on_updating_nonexistent_server() {
@@ -16,7 +17,11 @@ fn should_fails_on_updating_nonexistent_server() {
         index: None,
         username: None,
         password: None,
-        ask_password: false
+        ask_password: false,
+        auth_type: None,
+        token: None,
+        tls: TlsOptions::default(),
+        connection: ConnectionOptions::default()
     }, config);
 
     assert_eq!(Err(ConfigActionError::ServerDoesNotExists { server_name: "test".to_string() }), result);
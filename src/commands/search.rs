@@ -1,10 +1,15 @@
 use crate::config::{ApplicationConfig, ElasticSearchServer, ElasticSearchServerType, GetServerError};
 use crate::commands::Command;
-use crate::client::{Client, elastic::ElasticClient, kibana::KibanaProxyClient, stub::StubClient, SearchRequest};
+use crate::client::{
+    Client, file::FileClient, search::SearchClient, stub::StubClient, SearchRequest, TimeRange, Sort, VersionCache, VersionProbe,
+    ServerVersion, Distribution, DEFAULT_TIME_FIELD
+};
 use crate::display::*;
 
 use clap::ArgMatches;
 
+use std::io::stdout;
+use std::path::PathBuf;
 use std::string::ToString;
 use std::sync::Arc;
 use error::ApplicationError;
@@ -14,24 +19,47 @@ pub struct SearchCommand {
     pub client: Box<Client>,
     pub renderer: Box<Renderer>,
     pub request: SearchRequest,
-    pub secrets: Arc<SecretsReader>
+    /// Prints the requests instead of sending them
+    pub dry_run: bool
 }
 
 impl Command for SearchCommand {
     fn execute(&mut self) -> Result<(), ApplicationError> {
+        if self.dry_run {
+            return self.print_requests();
+        }
         info!("Executing search '{}' on index '{}'", self.request.query, self.request.index);
 
-        let _ = self.client.execute(&self.request).map_err(|err| {
-            error!("Cannot fetch items from server: {}", err)
-        }).map(|collector| {
-            self.renderer.render(collector);
-        });
+        let collector = self.client.execute(&self.request).map_err(|err| {
+            error!("Cannot fetch items from server: {}", err);
+            ApplicationError
+        })?;
 
-        Ok(())
+        self.renderer.render(collector, self.client.as_ref(), &self.request).map_err(|err| {
+            match err {
+                RenderError::Incomplete { .. } => error!("Output is truncated, {}", err),
+                _ => error!("Cannot render items: {}", err)
+            }
+            ApplicationError
+        })
     }
 }
 
 impl SearchCommand {
+    fn print_requests(&self) -> Result<(), ApplicationError> {
+        let requests = self.client.prepare(&self.request).map_err(|err| {
+            error!("Cannot prepare the request: {}", err);
+            ApplicationError
+        })?;
+        if requests.is_empty() {
+            warn!("The server sends no requests");
+        }
+        for request in requests {
+            println!("# curl\n{}\n\n# Kibana Dev Tools console\n{}\n", request.curl(), request.console());
+        }
+        Ok(())
+    }
+
     pub fn parse(config: &ApplicationConfig, secrets: Arc<SecretsReader>, matches: &ArgMatches, sub_match: &ArgMatches) -> Result<Self, ApplicationError> {
         let server = match config.get_server(matches.value_of("server")) {
             Ok(server) => Ok(server),
@@ -78,10 +106,42 @@ impl SearchCommand {
             .or_else(|| server.default_index.clone())
             .unwrap_or_else(|| "*".to_string());
 
+        let from = sub_match.value_of("from").map(ToString::to_string);
+        let to = sub_match.value_of("to").map(ToString::to_string);
+        let range = if from.is_some() || to.is_some() {
+            Some(TimeRange {
+                field: sub_match.value_of("time-field").unwrap_or(DEFAULT_TIME_FIELD).to_string(),
+                from,
+                to
+            })
+        } else {
+            None
+        };
+
+        let sort = sub_match.value_of("sort")
+            .map(Sort::parse)
+            .map_or(Ok(None), |sort| sort.map(Some))
+            .map_err(|err| {
+                error!("Argument 'sort' has invalid value: {}", err);
+                ApplicationError
+            })?;
+
+        let distribution = match server.server_type {
+            ElasticSearchServerType::OpenSearch | ElasticSearchServerType::OpenSearchDashboards => Distribution::OpenSearch,
+            _ => Distribution::Elasticsearch
+        };
+        let version = sub_match.value_of("server-version")
+            .map(|number| ServerVersion::from_number(distribution, number).ok_or_else(|| {
+                error!("Argument 'server-version' has invalid value: {}, expected the version like 7.17.0", number);
+                ApplicationError
+            }))
+            .map_or(Ok(None), |version| version.map(Some))?;
+
         let format = sub_match.value_of("output")
             .map(|f| match f {
                 "pretty" => OutputFormat::Pretty,
                 "json" => OutputFormat::JSON,
+                "tree" => OutputFormat::Tree,
                 custom => OutputFormat::Custom(custom.to_string())
             }).unwrap_or(OutputFormat::Pretty);
 
@@ -89,24 +149,36 @@ impl SearchCommand {
             .map(|s| JSONExtractor::filtered(s.split(',')))
             .unwrap_or_else(JSONExtractor::default);
 
-        let renderer = Self::create_renderer(pager_enabled, format, extractor);
-        let client = Self::create_client(secrets.clone(), server, buffer_size);
+        let keymap = Keymap::with_overrides(&config.pager.keys).map_err(|err| {
+            error!("Invalid key bindings of the pager in the configuration: {}", err);
+            ApplicationError
+        })?;
+
+        let renderer = Self::create_renderer(pager_enabled, format, extractor, &server.name, keymap);
+        let record = matches.value_of("record").map(PathBuf::from);
+        let client = Self::create_client(secrets, server, buffer_size, record, version, config.versions_path());
 
         Ok(SearchCommand {
             client,
-            request: SearchRequest { query, index },
+            request: SearchRequest { query, index, range, sort },
             renderer,
-            secrets
+            dry_run: matches.is_present("dry-run")
         })
     }
 
     fn create_renderer(
         pager_enabled: bool,
         format: OutputFormat,
-        extractor: JSONExtractor
+        extractor: JSONExtractor,
+        server_name: &str,
+        keymap: Keymap
     ) -> Box<Renderer> {
-        if pager_enabled {
-            Box::new(PagedRenderer::new(format, extractor))
+        let is_tty = termion::is_tty(&stdout());
+        if pager_enabled && !is_tty {
+            warn!("Output is not a terminal, the pager is disabled");
+        }
+        if pager_enabled && is_tty {
+            Box::new(PagedRenderer::new(format, extractor, server_name, keymap))
         } else {
             Box::new(SimpleRenderer::new(format, extractor))
         }
@@ -115,12 +187,25 @@ impl SearchCommand {
     fn create_client(
         secrets: Arc<SecretsReader>,
         server: &ElasticSearchServer,
-        buffer_size: usize
+        buffer_size: usize,
+        record: Option<PathBuf>,
+        version: Option<ServerVersion>,
+        versions_path: PathBuf
     ) -> Box<Client> {
         match server.server_type {
-            ElasticSearchServerType::Elastic => Box::new(ElasticClient::create(secrets, server.clone(), buffer_size)),
-            ElasticSearchServerType::Kibana => Box::new(KibanaProxyClient::create(secrets, server.clone(), buffer_size)),
-            ElasticSearchServerType::Stub => Box::new(StubClient::new(buffer_size))
+            ElasticSearchServerType::Stub | ElasticSearchServerType::File if record.is_some() => {
+                warn!("The {} server sends no requests, nothing is recorded", server.server_type.product_name());
+                Self::create_client(secrets, server, buffer_size, None, version, versions_path)
+            },
+            ElasticSearchServerType::Stub => Box::new(StubClient::new(buffer_size)),
+            ElasticSearchServerType::File => Box::new(FileClient::new(&server.server, buffer_size)),
+            _ => {
+                let version = match version {
+                    Some(version) => VersionProbe::known(version),
+                    None => VersionProbe::cached(VersionCache::new(versions_path, server))
+                };
+                Box::new(SearchClient::create(secrets, server.clone(), buffer_size, record, version))
+            }
         }
     }
 }
\ No newline at end of file
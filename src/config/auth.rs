@@ -0,0 +1,96 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Service name of the Amazon OpenSearch Service domains, `aoss` is used by OpenSearch Serverless
+pub const DEFAULT_AWS_SERVICE: &str = "es";
+
+/// Authentication of the requests to the server, the secrets are kept in the system keyring
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthType {
+    /// The username and the password stored by the username
+    Basic,
+    /// Elasticsearch API key, either encoded or as `id:api_key`
+    ApiKey,
+    /// Token sent as `Authorization: Bearer <token>`
+    Bearer,
+    /// Requests signed by AWS Signature Version 4
+    #[serde(rename = "aws_sigv4")]
+    AwsSigV4(AwsSigV4Config),
+    None
+}
+
+/// Settings of the AWS request signing, the credentials are read from the keyring, the environment or the profile files
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AwsSigV4Config {
+    pub region: String,
+    #[serde(default = "default_aws_service")]
+    pub service: String,
+    /// Profile of the AWS shared credentials and config files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>
+}
+
+fn default_aws_service() -> String {
+    DEFAULT_AWS_SERVICE.to_string()
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "unknown auth type: {}, expected basic, api_key, bearer, aws_sigv4 or none", value)]
+pub struct UnknownAuthTypeError {
+    value: String
+}
+
+impl FromStr for AuthType {
+    type Err = UnknownAuthTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "basic" => Ok(AuthType::Basic),
+            "api_key" => Ok(AuthType::ApiKey),
+            "bearer" => Ok(AuthType::Bearer),
+            "none" => Ok(AuthType::None),
+            value => Err(UnknownAuthTypeError { value: value.to_string() })
+        }
+    }
+}
+
+impl fmt::Display for AuthType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AuthType::Basic => "basic",
+            AuthType::ApiKey => "api_key",
+            AuthType::Bearer => "bearer",
+            AuthType::AwsSigV4(_) => "aws_sigv4",
+            AuthType::None => "none"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthType, AwsSigV4Config};
+    use serde_yaml;
+
+    use std::str::FromStr;
+
+    #[test]
+    fn it_should_read_auth_section() {
+        let auth: AuthType = serde_yaml::from_str("type: api_key").unwrap();
+        assert_eq!(AuthType::ApiKey, auth);
+        assert!(serde_yaml::to_string(&AuthType::Bearer).unwrap().contains("type: bearer"));
+    }
+
+    #[test]
+    fn it_should_read_aws_settings_with_default_service() {
+        let auth: AuthType = serde_yaml::from_str("type: aws_sigv4\nregion: eu-west-1").unwrap();
+        let aws = AwsSigV4Config { region: "eu-west-1".to_string(), service: "es".to_string(), profile: None };
+        assert_eq!(AuthType::AwsSigV4(aws), auth);
+    }
+
+    #[test]
+    fn it_should_parse_auth_type_argument() {
+        assert_eq!(AuthType::None, AuthType::from_str("none").unwrap());
+        assert!(AuthType::from_str("token").is_err());
+    }
+}
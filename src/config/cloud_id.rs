@@ -0,0 +1,103 @@
+use std::str::FromStr;
+
+const DEFAULT_PORT: u16 = 443;
+
+/// Endpoints of the Elastic Cloud deployment encoded in `<deployment name>:<base64 of host$elasticsearch id$kibana id>`
+#[derive(Debug, PartialEq, Clone)]
+pub struct CloudId {
+    pub name: String,
+    pub elasticsearch_url: String,
+    pub kibana_url: Option<String>
+}
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum CloudIdError {
+    #[fail(display = "cloud ID should be '<deployment name>:<base64 data>', the colon is missing")]
+    MissingSeparator,
+    #[fail(display = "data of the cloud ID is not valid base64: {}", inner)]
+    InvalidBase64 { inner: String },
+    #[fail(display = "data of the cloud ID should be '<host>$<elasticsearch id>$<kibana id>', got '{}'", decoded)]
+    InvalidFormat { decoded: String },
+    #[fail(display = "port of the cloud ID is not a number: {}", port)]
+    InvalidPort { port: String },
+    #[fail(display = "cloud ID has no Kibana endpoint")]
+    NoKibana
+}
+
+impl FromStr for CloudId {
+    type Err = CloudIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let position = s.find(':').ok_or(CloudIdError::MissingSeparator)?;
+        let (name, data) = (&s[..position], &s[position + 1..]);
+        let decoded = base64::decode(data)
+            .map_err(|err| CloudIdError::InvalidBase64 { inner: err.to_string() })
+            .and_then(|bytes| String::from_utf8(bytes).map_err(|err| CloudIdError::InvalidBase64 { inner: err.to_string() }))?;
+        let invalid_format = || CloudIdError::InvalidFormat { decoded: decoded.clone() };
+
+        let parts: Vec<&str> = decoded.trim_end_matches('$').split('$').collect();
+        let (domain, elasticsearch_id, kibana_id) = match parts.as_slice() {
+            [domain, elasticsearch_id] => (*domain, *elasticsearch_id, None),
+            [domain, elasticsearch_id, kibana_id, ..] => (*domain, *elasticsearch_id, Some(*kibana_id)),
+            _ => return Err(invalid_format())
+        };
+        if domain.is_empty() || elasticsearch_id.is_empty() {
+            return Err(invalid_format());
+        }
+        let (host, port) = match domain.rfind(':') {
+            Some(position) => {
+                let port = &domain[position + 1..];
+                (&domain[..position], port.parse::<u16>().map_err(|_| CloudIdError::InvalidPort { port: port.to_string() })?)
+            },
+            None => (domain, DEFAULT_PORT)
+        };
+        let url = |id: &str| if port == DEFAULT_PORT {
+            format!("https://{}.{}", id, host)
+        } else {
+            format!("https://{}.{}:{}", id, host, port)
+        };
+
+        Ok(CloudId {
+            name: name.to_string(),
+            elasticsearch_url: url(elasticsearch_id),
+            kibana_url: kibana_id.filter(|id| !id.is_empty()).map(url)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CloudId, CloudIdError};
+
+    use std::str::FromStr;
+
+    fn cloud_id(data: &str) -> String {
+        format!("logs:{}", base64::encode(data))
+    }
+
+    #[test]
+    fn it_should_decode_endpoints() {
+        let cloud_id = CloudId::from_str(&cloud_id("us-east-1.aws.found.io$es-id$kibana-id")).unwrap();
+        assert_eq!(CloudId {
+            name: "logs".to_string(),
+            elasticsearch_url: "https://es-id.us-east-1.aws.found.io".to_string(),
+            kibana_url: Some("https://kibana-id.us-east-1.aws.found.io".to_string())
+        }, cloud_id);
+    }
+
+    #[test]
+    fn it_should_keep_custom_port() {
+        let cloud_id = CloudId::from_str(&cloud_id("westeurope.azure.elastic-cloud.com:9243$es-id")).unwrap();
+        assert_eq!("https://es-id.westeurope.azure.elastic-cloud.com:9243", cloud_id.elasticsearch_url);
+        assert_eq!(None, cloud_id.kibana_url);
+    }
+
+    #[test]
+    fn it_should_explain_malformed_ids() {
+        assert_eq!(Err(CloudIdError::MissingSeparator), CloudId::from_str("bG9ncw=="));
+        assert!(matches!(CloudId::from_str("logs:not base64!"), Err(CloudIdError::InvalidBase64 { .. })));
+        assert_eq!(Err(CloudIdError::InvalidFormat { decoded: "found.io".to_string() }), CloudId::from_str(&cloud_id("found.io")));
+        assert_eq!(Err(CloudIdError::InvalidPort { port: "https".to_string() }), CloudId::from_str(&cloud_id("found.io:https$es-id")));
+    }
+}
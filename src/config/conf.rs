@@ -1,5 +1,6 @@
 use crate::config;
 
+use std::collections::BTreeMap;
 use std::fs::{File, OpenOptions};
 use std::vec::Vec;
 use std::path::{Path, PathBuf};
@@ -14,16 +15,79 @@ pub struct ApplicationConfig {
 
     pub default_server: Option<String>,
     pub servers: Vec<ElasticSearchServer>,
+    #[serde(default, skip_serializing_if = "config::PagerConfig::is_empty")]
+    pub pager: config::PagerConfig
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
 pub struct ElasticSearchServer {
     pub name: String,
+    /// Address of the server, empty if the cloud ID is set
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub server: String,
+    /// Elastic Cloud ID, the address is decoded from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud_id: Option<String>,
     #[serde(default)]
     pub server_type: config::ElasticSearchServerType,
     pub default_index: Option<String>,
-    pub username: Option<String>
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<config::AuthType>,
+    #[serde(default, skip_serializing_if = "config::TlsConfig::is_empty")]
+    pub tls: config::TlsConfig,
+    /// HTTP proxy, the proxy from the environment variables is used if it is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Hosts which are connected directly, in addition to the `NO_PROXY` environment variable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+    /// Headers sent with every request
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, config::HeaderValueSource>,
+    /// Tenant of the security plugin of Kibana and OpenSearch Dashboards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(default, skip_serializing_if = "config::RequestConfig::is_empty")]
+    pub request: config::RequestConfig
+}
+
+impl ElasticSearchServer {
+    /// Address of the server, or the endpoint of the cloud deployment for the server type
+    pub fn address(&self) -> Result<String, config::CloudIdError> {
+        let cloud_id = match &self.cloud_id {
+            Some(cloud_id) => cloud_id.parse::<config::CloudId>()?,
+            None => return Ok(self.server.clone())
+        };
+        match self.server_type {
+            config::ElasticSearchServerType::Kibana => cloud_id.kibana_url.ok_or(config::CloudIdError::NoKibana),
+            _ => Ok(cloud_id.elasticsearch_url)
+        }
+    }
+
+    /// Configurations without the `auth` section use the basic auth if the username is set
+    pub fn auth_type(&self) -> config::AuthType {
+        match (&self.auth, &self.username) {
+            (Some(auth), _) => auth.clone(),
+            (None, Some(_)) => config::AuthType::Basic,
+            (None, None) => config::AuthType::None
+        }
+    }
+
+    /// Key of the API key, the bearer token or the AWS credentials of the server in the keyring
+    pub fn token_key(&self) -> String {
+        format!("{}/{}", self.name, self.auth_type())
+    }
+
+    /// Key of the secret value of the header in the keyring
+    pub fn header_key(&self, header: &str) -> String {
+        format!("{}/headers/{}", self.name, header)
+    }
+
+    /// Key of the password of the PKCS#12 client certificate in the keyring
+    pub fn client_cert_password_key(&self) -> String {
+        format!("{}/client_cert", self.name)
+    }
 }
 
 impl ApplicationConfig {
@@ -41,7 +105,8 @@ impl ApplicationConfig {
             let config = ApplicationConfig {
                 file_path: path.to_owned(),
                 default_server: None,
-                servers: vec![]
+                servers: vec![],
+                pager: config::PagerConfig::default()
             };
             return config.save_file().map(|_| config);
         }
@@ -78,6 +143,11 @@ impl ApplicationConfig {
             .ok_or_else(||config::GetServerError::ServerNotFound { server: server_name.clone() })
     }
 
+    /// File of the server versions detected by the searches, next to the configuration file
+    pub fn versions_path(&self) -> PathBuf {
+        PathBuf::from(format!("{}.versions", self.file_path))
+    }
+
     pub fn save_file(&self) -> Result<(), config::Error> {
         let mut file = self.open_file_or_create()?;
         let yaml = serde_yaml::to_string(self).map_err(|err| {
@@ -92,7 +162,7 @@ impl ApplicationConfig {
 
     fn open_file_or_create(&self) -> Result<File, config::Error> {
         if Path::new(&self.file_path).exists() {
-            OpenOptions::new().write(true).open(&self.file_path).map_err(|err| {
+            OpenOptions::new().write(true).truncate(true).open(&self.file_path).map_err(|err| {
                 error!("Cannot open configuration file {}: {}", self.file_path, err);
                 config::Error::IOError { inner: err }
             })
@@ -103,4 +173,27 @@ impl ApplicationConfig {
             })
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn save_file_should_replace_longer_content() {
+        let path = env::temp_dir().join(format!("elastic-cli-config-{}.yml", std::process::id()));
+        fs::write(&path, format!("---\ndefault_server: production\nservers: []\n# {}\n", "x".repeat(100))).unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let mut config = ApplicationConfig::load_file(&path).unwrap();
+        config.default_server = None;
+        config.save_file().unwrap();
+
+        assert_eq!(serde_yaml::to_string(&config).unwrap(), fs::read_to_string(&path).unwrap());
+        assert_eq!(config, ApplicationConfig::load_file(&path).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+}
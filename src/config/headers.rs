@@ -0,0 +1,23 @@
+/// Value of the custom header of the server, secret values are kept in the system keyring
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum HeaderValueSource {
+    Plain(String),
+    /// The value is read from the keyring by the key
+    Keyring { keyring: String }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeaderValueSource;
+    use serde_yaml;
+
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn it_should_read_plain_and_secret_values() {
+        let headers: BTreeMap<String, HeaderValueSource> = serde_yaml::from_str("X-Tenant: team\nX-Token:\n  keyring: prod/X-Token\n").unwrap();
+        assert_eq!(Some(&HeaderValueSource::Plain("team".to_string())), headers.get("X-Tenant"));
+        assert_eq!(Some(&HeaderValueSource::Keyring { keyring: "prod/X-Token".to_string() }), headers.get("X-Token"));
+    }
+}
@@ -2,8 +2,20 @@ mod conf;
 mod error;
 mod server_type;
 mod secrets;
+mod pager;
+mod auth;
+mod tls;
+mod headers;
+mod request;
+mod cloud_id;
 
 pub use self::conf::{ApplicationConfig, ElasticSearchServer};
 pub use self::error::{Error, GetServerError};
 pub use self::server_type::ElasticSearchServerType;
-pub use self::secrets::*;
\ No newline at end of file
+pub use self::secrets::*;
+pub use self::pager::PagerConfig;
+pub use self::auth::{AuthType, AwsSigV4Config, DEFAULT_AWS_SERVICE};
+pub use self::tls::TlsConfig;
+pub use self::headers::HeaderValueSource;
+pub use self::request::RequestConfig;
+pub use self::cloud_id::{CloudId, CloudIdError};
\ No newline at end of file
@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+/// Settings of the interactive pager
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct PagerConfig {
+    /// Keys of the actions replacing the default ones, e.g. `page_down: [ctrl-f, space]`
+    #[serde(default)]
+    pub keys: BTreeMap<String, Vec<String>>
+}
+
+impl PagerConfig {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PagerConfig;
+    use serde_yaml;
+
+    #[test]
+    fn it_should_read_keys_of_actions() {
+        let config: PagerConfig = serde_yaml::from_str("keys:\n  page_down: [ctrl-v, space]\n").unwrap();
+        assert_eq!(Some(&vec!["ctrl-v".to_string(), "space".to_string()]), config.keys.get("page_down"));
+    }
+}
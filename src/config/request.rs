@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_READ_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Timeouts and retries of the requests to the server, the defaults are used for the missing values
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct RequestConfig {
+    /// Seconds to establish the connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+    /// Seconds to wait for the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_timeout: Option<u64>,
+    /// Retries of the failed request, 0 disables them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>
+}
+
+impl RequestConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == RequestConfig::default()
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS))
+    }
+
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_secs(self.read_timeout.unwrap_or(DEFAULT_READ_TIMEOUT_SECS))
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(DEFAULT_MAX_RETRIES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RequestConfig;
+    use serde_yaml;
+
+    use std::time::Duration;
+
+    #[test]
+    fn it_should_use_defaults_for_missing_values() {
+        let request: RequestConfig = serde_yaml::from_str("read_timeout: 5\nmax_retries: 0\n").unwrap();
+        assert_eq!(Duration::from_secs(10), request.connect_timeout());
+        assert_eq!(Duration::from_secs(5), request.read_timeout());
+        assert_eq!(0, request.max_retries());
+    }
+}
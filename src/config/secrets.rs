@@ -1,6 +1,6 @@
 use keyring::{Keyring, KeyringError};
 
-pub trait SecretsReader {
+pub trait SecretsReader: Send + Sync {
     fn read(&self, key: &str) -> Result<Option<String>, ReadSecretError>;
 
     fn get_credentials(&self, username: &str) -> Result<Option<Credentials>, ReadSecretError>;
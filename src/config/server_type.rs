@@ -8,6 +8,12 @@ use std::str::FromStr;
 pub enum ElasticSearchServerType {
     Elastic,
     Kibana,
+    OpenSearch,
+    OpenSearchDashboards,
+    /// Responses recorded by `--record`, the address is the directory
+    Replay,
+    /// Local NDJSON files, the address is the path or the glob
+    File,
     Stub
 }
 
@@ -24,6 +30,10 @@ impl Serialize for ElasticSearchServerType {
        match self {
            ElasticSearchServerType::Elastic => s.serialize_str("elastic"),
            ElasticSearchServerType::Kibana => s.serialize_str("kibana"),
+           ElasticSearchServerType::OpenSearch => s.serialize_str("opensearch"),
+           ElasticSearchServerType::OpenSearchDashboards => s.serialize_str("opensearch-dashboards"),
+           ElasticSearchServerType::Replay => s.serialize_str("replay"),
+           ElasticSearchServerType::File => s.serialize_str("file"),
            ElasticSearchServerType::Stub => s.serialize_str("stub"),
        }
     }
@@ -39,7 +49,7 @@ impl<'de> Visitor<'de> for ElasticSearchServerTypeVisitor {
     type Value = ElasticSearchServerType;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("'elastic' or 'kibana'")
+        formatter.write_str("'elastic', 'kibana', 'opensearch', 'opensearch-dashboards', 'replay' or 'file'")
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: de::Error {
@@ -61,8 +71,68 @@ impl FromStr for ElasticSearchServerType {
         match s {
             "elastic" => Ok(ElasticSearchServerType::Elastic),
             "kibana" => Ok(ElasticSearchServerType::Kibana),
+            "opensearch" => Ok(ElasticSearchServerType::OpenSearch),
+            "opensearch-dashboards" => Ok(ElasticSearchServerType::OpenSearchDashboards),
+            "replay" => Ok(ElasticSearchServerType::Replay),
+            "file" => Ok(ElasticSearchServerType::File),
             "stub" => Ok(ElasticSearchServerType::Stub),
             value => Err(UnknownElasticSearchServerTypeError { value: value.to_string() })
         }
     }
-}
\ No newline at end of file
+}
+
+impl ElasticSearchServerType {
+    /// Name of the server in the messages
+    pub fn product_name(&self) -> &'static str {
+        match self {
+            ElasticSearchServerType::Elastic => "elasticsearch",
+            ElasticSearchServerType::Kibana => "kibana",
+            ElasticSearchServerType::OpenSearch => "opensearch",
+            ElasticSearchServerType::OpenSearchDashboards => "opensearch dashboards",
+            ElasticSearchServerType::Replay => "replay",
+            ElasticSearchServerType::File => "file",
+            ElasticSearchServerType::Stub => "stub"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ElasticSearchServerType;
+    use serde_yaml;
+
+    use std::str::FromStr;
+
+    #[test]
+    fn it_should_parse_server_types() {
+        assert_eq!(ElasticSearchServerType::OpenSearch, ElasticSearchServerType::from_str("opensearch").unwrap());
+        assert_eq!(ElasticSearchServerType::OpenSearchDashboards, ElasticSearchServerType::from_str("opensearch-dashboards").unwrap());
+        assert_eq!(ElasticSearchServerType::Kibana, ElasticSearchServerType::from_str("kibana").unwrap());
+        assert!(ElasticSearchServerType::from_str("opensearch_dashboards").is_err());
+    }
+
+    #[test]
+    fn it_should_serialize_server_types() {
+        let types = vec![
+            ElasticSearchServerType::Elastic,
+            ElasticSearchServerType::Kibana,
+            ElasticSearchServerType::OpenSearch,
+            ElasticSearchServerType::OpenSearchDashboards,
+            ElasticSearchServerType::Replay,
+            ElasticSearchServerType::File,
+            ElasticSearchServerType::Stub
+        ];
+        let yaml = serde_yaml::to_string(&types).unwrap();
+        assert!(yaml.contains("- opensearch\n"));
+        assert!(yaml.contains("- opensearch-dashboards"));
+        assert!(yaml.contains("- replay"));
+        assert!(yaml.contains("- file"));
+        assert_eq!(types, serde_yaml::from_str::<Vec<ElasticSearchServerType>>(&yaml).unwrap());
+    }
+
+    #[test]
+    fn it_should_explain_unknown_server_type() {
+        let err = serde_yaml::from_str::<ElasticSearchServerType>("elasticsearch").unwrap_err();
+        assert!(err.to_string().contains("unknown server type: elasticsearch"));
+    }
+}
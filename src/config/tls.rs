@@ -0,0 +1,41 @@
+/// TLS settings of the server, the certificates are PEM or DER files
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct TlsConfig {
+    /// Certificate of the private CA, trusted in addition to the system ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+    /// Client certificate for mutual TLS, PEM or PKCS#12
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<String>,
+    /// Private key of the PEM client certificate, if the key is not in the certificate file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
+    /// Disables the verification of the server certificate and the host name
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub insecure_skip_verify: bool
+}
+
+impl TlsConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == TlsConfig::default()
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TlsConfig;
+    use serde_yaml;
+
+    #[test]
+    fn it_should_skip_default_values() {
+        let tls = TlsConfig { ca_cert: Some("ca.pem".to_string()), ..TlsConfig::default() };
+        let yaml = serde_yaml::to_string(&tls).unwrap();
+        assert!(yaml.contains("ca_cert: ca.pem"));
+        assert!(!yaml.contains("insecure_skip_verify"));
+        assert_eq!(tls, serde_yaml::from_str(&yaml).unwrap());
+    }
+}
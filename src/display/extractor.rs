@@ -1,12 +1,10 @@
 use serde_json::Value;
-use std::iter::FromIterator;
 use std::convert::Into;
-use std::collections::HashSet;
 use std::collections::btree_map::BTreeMap;
 
 pub struct JSONExtractor {
     field_delimiter: String,
-    fields: Option<HashSet<String>>
+    fields: Option<Vec<String>>
 }
 
 impl JSONExtractor {
@@ -20,16 +18,32 @@ impl JSONExtractor {
     pub fn filtered<S: Into<String>, I: IntoIterator<Item = S>>(fields: I) -> Self {
         JSONExtractor {
             field_delimiter: ".".to_string(),
-            fields: Some(HashSet::from_iter(fields.into_iter().map(Into::into)))
+            fields: Some(fields.into_iter().map(Into::into).collect())
         }
     }
 
+    /// Fields passed to `filtered`, in the same order
+    pub fn fields(&self) -> Option<&[String]> {
+        self.fields.as_deref()
+    }
+
     pub fn extract(&self, item: &Value) -> BTreeMap<String, String> {
         let mut map = BTreeMap::new();
         self.extract_one(&[], item, &mut map);
         map
     }
 
+    /// Same as `extract`, but the filtered fields keep their order, otherwise fields are sorted by name
+    pub fn extract_ordered(&self, item: &Value) -> Vec<(String, String)> {
+        let mut map = self.extract(item);
+        match &self.fields {
+            Some(fields) => fields.iter()
+                .filter_map(|field| map.remove(field).map(|value| (field.clone(), value)))
+                .collect(),
+            None => map.into_iter().collect()
+        }
+    }
+
     fn extract_one(&self, path: &[String], hit: &Value, map: &mut BTreeMap<String, String>) {
         match hit {
             &Value::Object(ref object) => {
@@ -58,7 +72,7 @@ impl JSONExtractor {
     fn is_field_ok(&self, field: &str) -> bool {
         self.fields
             .as_ref()
-            .map(|f| f.contains(field))
+            .map(|f| f.iter().any(|f| f == field))
             .unwrap_or(true)
     }
 
@@ -125,4 +139,19 @@ mod tests {
         assert_eq!(None, map.get("root.arr.3.value"));
     }
 
+    #[test]
+    fn it_should_keep_order_of_filtered_fields() {
+        let fields = vec!["root.obj.strKey", "root.arr.2.value", "root.missing"];
+        let values = JSONExtractor::filtered(fields)
+            .extract_ordered(&get_value());
+
+        assert_eq!(
+            vec![
+                ("root.obj.strKey".to_string(), "str1".to_string()),
+                ("root.arr.2.value".to_string(), "3".to_string())
+            ],
+            values
+        );
+    }
+
 }
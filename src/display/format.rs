@@ -1,15 +1,18 @@
-use super::{JSONExtractor};
+use super::{JSONExtractor, TreeRow, tree_rows, select_subtrees};
 
 use serde_json::Value;
 use strfmt::strfmt;
 use colored::*;
 
 use std::fmt::Write;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
+#[derive(Clone)]
 pub enum OutputFormat {
     JSON,
     Pretty,
+    /// Nested objects and arrays indented with guides
+    Tree,
     Custom(String)
 }
 
@@ -26,6 +29,32 @@ impl Formatter {
         }
     }
 
+    /// Creates the formatter with the same output format and another set of fields
+    pub fn with_extractor(&self, extractor: JSONExtractor) -> Self {
+        Self::new(self.format.clone(), extractor)
+    }
+
+    pub fn extractor(&self) -> &JSONExtractor {
+        &self.extractor
+    }
+
+    /// JSON format prints the whole item, so the fields don't matter for it
+    pub fn uses_fields(&self) -> bool {
+        !matches!(self.format, OutputFormat::JSON)
+    }
+
+    pub fn is_tree(&self) -> bool {
+        matches!(self.format, OutputFormat::Tree)
+    }
+
+    /// Tree of the item limited to the subtrees of the fields, if they are set
+    pub fn format_tree(&self, item: &Value, collapsed: &BTreeSet<String>) -> Vec<TreeRow> {
+        match self.extractor.fields() {
+            Some(fields) => tree_rows(&select_subtrees(item, fields), collapsed),
+            None => tree_rows(item, collapsed)
+        }
+    }
+
     pub fn format(&self, item: &Value, index: usize) -> String {
         let mut str = String::new();
         match &self.format {
@@ -33,11 +62,18 @@ impl Formatter {
                 if index > 0 {
                     let _ = writeln!(str, "{}", "-".repeat(4).blue().bold());
                 }
-                let map = self.extractor.extract(item);
-                for (key, value) in map {
+                for (key, value) in self.extractor.extract_ordered(item) {
                     let _ = writeln!(str, "{}: {}", key.green().bold(), format_string(&value));
                 }
             },
+            OutputFormat::Tree => {
+                if index > 0 {
+                    let _ = writeln!(str, "{}", "-".repeat(4).blue().bold());
+                }
+                for row in self.format_tree(item, &BTreeSet::new()) {
+                    let _ = writeln!(str, "{}", row.line);
+                }
+            },
             OutputFormat::JSON => {
                 let _ = writeln!(str, "{}", item);
             },
@@ -125,6 +161,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn renderer_pretty_format_should_render_fields_in_the_given_order() {
+        let str = Formatter::new(OutputFormat::Pretty, JSONExtractor::filtered(vec!["b", "a"]))
+            .format(&json!({ "a": 1, "b": 2 }), 0);
+
+        assert_eq!(format!("{}: {}\n{}: {}\n", "b".green().bold(), "2", "a".green().bold(), "1"), str);
+    }
+
+    #[test]
+    fn renderer_tree_format_should_render_subtrees_of_fields() {
+        let str = Formatter::new(OutputFormat::Tree, JSONExtractor::filtered(vec!["root.obj"]))
+            .format(&json!({ "root": { "obj": { "a": 1 }, "b": 2 } }), 1);
+
+        assert_eq!(
+            format!("{}\n└── {}\n    └── {}\n        └── {}: 1\n", "----".blue().bold(), "root".green().bold(), "obj".green().bold(), "a".green().bold()),
+            str
+        );
+    }
+
     #[test]
     fn format_string_should_replaces_new_line_and_tab_placeholders_to_real_symbols() {
         assert_eq!(
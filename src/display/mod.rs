@@ -1,10 +1,12 @@
 mod extractor;
 mod renderer;
 mod format;
+mod tree;
 mod pager;
 
 pub use self::extractor::*;
 pub use self::renderer::*;
 pub use self::format::*;
+pub use self::tree::*;
 pub use self::pager::*;
 
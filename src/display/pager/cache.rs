@@ -0,0 +1,92 @@
+//! Formatted lines of the items, so redraws don't format the same items again
+
+use crate::display::Formatter;
+use super::text::wrap_line;
+
+use serde_json::Value;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub struct LinesCache {
+    formatter: Arc<Formatter>,
+    wrap_width: Option<usize>,
+    blocks: HashMap<usize, Rc<Vec<String>>>
+}
+
+impl LinesCache {
+    pub fn new(formatter: Arc<Formatter>) -> Self {
+        LinesCache {
+            formatter,
+            wrap_width: None,
+            blocks: HashMap::new()
+        }
+    }
+
+    /// Drops the cached lines if the formatter or the wrap width differs from the cached ones
+    pub fn configure(&mut self, formatter: &Arc<Formatter>, wrap_width: Option<usize>) {
+        if !Arc::ptr_eq(&self.formatter, formatter) || self.wrap_width != wrap_width {
+            self.formatter = formatter.clone();
+            self.wrap_width = wrap_width;
+            self.clear();
+        }
+    }
+
+    /// Must be called when the items are replaced
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    /// Lines of the item, tabs are expanded and long lines are wrapped if the wrap width is set
+    pub fn lines(&mut self, index: usize, item: &Value) -> Rc<Vec<String>> {
+        let LinesCache { formatter, wrap_width, blocks } = self;
+        blocks.entry(index)
+            .or_insert_with(|| {
+                let lines = formatter.format(item, index)
+                    .lines()
+                    .map(|line| line.replace('\t', "    "))
+                    .flat_map(|line| match wrap_width {
+                        Some(width) => wrap_line(&line, *width),
+                        None => vec![line]
+                    })
+                    .collect();
+                Rc::new(lines)
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::{OutputFormat, JSONExtractor};
+
+    fn formatter(format: &str) -> Arc<Formatter> {
+        Arc::new(Formatter::new(OutputFormat::Custom(format.to_string()), JSONExtractor::default()))
+    }
+
+    #[test]
+    fn it_should_format_item_once() {
+        let formatter = formatter("{text}");
+        let mut cache = LinesCache::new(formatter.clone());
+        assert_eq!(vec!["a", "b"], *cache.lines(0, &json!({ "text": "a\nb" })));
+        // the cached lines are returned for the same index
+        assert_eq!(vec!["a", "b"], *cache.lines(0, &json!({ "text": "c" })));
+
+        cache.configure(&formatter, None);
+        assert_eq!(vec!["a", "b"], *cache.lines(0, &json!({ "text": "c" })));
+    }
+
+    #[test]
+    fn it_should_invalidate_lines_on_format_or_width_change() {
+        let mut cache = LinesCache::new(formatter("{text}"));
+        assert_eq!(vec!["a    b"], *cache.lines(0, &json!({ "text": "a\tb" })));
+
+        cache.configure(&formatter("{text}"), None);
+        assert_eq!(vec!["abcd"], *cache.lines(0, &json!({ "text": "abcd" })));
+
+        cache.configure(&formatter("{text}"), Some(2));
+        assert_eq!(vec!["ab", "cd"], *cache.lines(0, &json!({ "text": "abcd" })));
+    }
+}
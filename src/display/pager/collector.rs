@@ -1,14 +1,10 @@
-use crate::display::format::Formatter;
 use crate::utils::{OptionalSkip, OptionalTake};
 
-use serde_json::Value;
-
 use std::cmp::max;
-use std::sync::Arc;
+use std::rc::Rc;
 
+/// Collects the lines of the visible window, lines of every item are requested by its index
 pub struct LinesCollector {
-    formatter: Arc<Formatter>,
-
     skip_items: Option<usize>,
     take_items: Option<usize>,
     take_lines: Option<usize>,
@@ -17,16 +13,15 @@ pub struct LinesCollector {
 
 pub struct CollectedLines {
     pub lines: Vec<String>,
+    /// Index of the item for each collected line
+    pub item_indexes: Vec<usize>,
     pub items_count: usize,
     pub has_cropped_items: bool
 }
 
 impl LinesCollector {
-    pub fn new(
-        formatter: Arc<Formatter>,
-    ) -> Self {
+    pub fn new() -> Self {
         Self {
-            formatter,
             skip_items: None,
             take_items: None,
             take_lines: None,
@@ -54,49 +49,180 @@ impl LinesCollector {
         self
     }
 
-    pub fn collect(self, source: impl Iterator<Item=Value>) -> CollectedLines {
-        let mut real_lines_count: usize = 0;
-        let mut items_count: usize = 0;
-        let mut items_sizes: Vec<usize> = vec![];
-
-        let mut lines: Vec<String> = source
-            .skip_by_option(self.skip_items)
-            .take_by_option(self.take_items)
-            .enumerate()
-            .map(|(index, item)| self.formatter.format(&item, index))
-            .map(|text| {
-                text.lines()
-                    .map(ToString::to_string)
-                    .collect::<Vec<String>>()
-            })
-            .filter(|item_lines| {
-                items_count += 1;
-                real_lines_count += item_lines.len();
-                items_sizes.push(item_lines.len());
-                true
-            })
-            .flat_map(|item_lines| item_lines)
-            .take_by_option(self.take_lines)
-            .collect();
+    /// Only the items of the window are requested: the ones after the skipped items for `take_lines`,
+    /// or the ones before the end of the taken items for `take_last_lines`.
+    /// `item_lines` returns None when there is no item with the index.
+    pub fn collect(self, mut item_lines: impl FnMut(usize) -> Option<Rc<Vec<String>>>) -> CollectedLines {
+        let first_item = self.skip_items.unwrap_or(0);
+
+        let blocks: Vec<(usize, Rc<Vec<String>>)> = match self.take_last_lines {
+            Some(limit) => {
+                let end = match self.take_items {
+                    Some(n) => first_item + n,
+                    // the end is unknown, so all items have to be requested
+                    None => (first_item..).find(|&index| item_lines(index).is_none()).unwrap_or(first_item)
+                };
+                let mut blocks = vec![];
+                let mut lines_count = 0;
+                for index in (first_item..end).rev() {
+                    if lines_count >= limit {
+                        break;
+                    }
+                    match item_lines(index) {
+                        Some(lines) => {
+                            lines_count += lines.len();
+                            blocks.push((index, lines));
+                        },
+                        None => continue
+                    }
+                }
+                blocks.reverse();
+                blocks
+            },
+            None => {
+                let mut blocks = vec![];
+                let mut lines_count = 0;
+                for index in (0..).skip_by_option(self.skip_items).take_by_option(self.take_items) {
+                    if self.take_lines.map(|limit| lines_count >= limit).unwrap_or(false) {
+                        break;
+                    }
+                    match item_lines(index) {
+                        Some(lines) => {
+                            lines_count += lines.len();
+                            blocks.push((index, lines));
+                        },
+                        None => break
+                    }
+                }
+                blocks
+            }
+        };
+
+        let items_count = blocks.len();
+        let real_lines_count: usize = blocks.iter().map(|(_, lines)| lines.len()).sum();
 
-        if let Some(take_last_lines) = self.take_last_lines {
-            let lines_to_skip = max(lines.len(), take_last_lines) - take_last_lines;
-            let mut total = 0;
-            items_count = items_sizes.iter()
-                .skip_while(|c| {
-                    total += *c;
-                    real_lines_count -= *c;
-                    total < lines_to_skip
-                }).count();
-            lines.drain(..lines_to_skip);
+        let mut lines: Vec<(usize, String)> = blocks.iter()
+            .flat_map(|(index, lines)| lines.iter().map(move |line| (*index, line.clone())))
+            .collect();
+        if let Some(limit) = self.take_last_lines {
+            lines.drain(..max(lines.len(), limit) - limit);
+        }
+        if let Some(limit) = self.take_lines {
+            lines.truncate(limit);
         }
 
         let lines_count = lines.len();
+        let (item_indexes, lines) = lines.into_iter().unzip();
 
         CollectedLines {
             lines,
+            item_indexes,
             items_count,
             has_cropped_items: real_lines_count != lines_count
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{LinesCollector, CollectedLines};
+    use display::pager::text::wrap_line;
+
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Creates items with the given count of lines, e.g. item 1 with 2 lines is "1.0\n1.1"
+    fn items(sizes: &[usize]) -> Vec<Rc<Vec<String>>> {
+        sizes.iter().enumerate()
+            .map(|(index, size)| Rc::new((0..*size).map(|line| format!("{}.{}", index, line)).collect()))
+            .collect()
+    }
+
+    fn wrapped_items(sizes: &[usize], width: usize) -> Vec<Rc<Vec<String>>> {
+        items(sizes).into_iter()
+            .map(|lines| Rc::new(lines.iter().flat_map(|line| wrap_line(line, width)).collect()))
+            .collect()
+    }
+
+    fn source(items: Vec<Rc<Vec<String>>>) -> impl FnMut(usize) -> Option<Rc<Vec<String>>> {
+        move |index| items.get(index).cloned()
+    }
+
+    fn collector() -> LinesCollector {
+        LinesCollector::new()
+    }
+
+    fn assert_lines(expected: &[&str], item_indexes: &[usize], items_count: usize, has_cropped_items: bool, actual: CollectedLines) {
+        assert_eq!(expected.iter().map(ToString::to_string).collect::<Vec<_>>(), actual.lines);
+        assert_eq!(item_indexes.to_vec(), actual.item_indexes);
+        assert_eq!(items_count, actual.items_count);
+        assert_eq!(has_cropped_items, actual.has_cropped_items);
+    }
+
+    #[test]
+    fn it_should_take_lines_from_top_and_crop_last_item() {
+        let lines = collector().skip_items(1).take_lines(3).collect(source(items(&[2, 2, 2])));
+        assert_lines(&["1.0", "1.1", "2.0"], &[1, 1, 2], 2, true, lines);
+    }
+
+    #[test]
+    fn it_should_not_crop_items_when_lines_fit_exactly() {
+        let lines = collector().skip_items(1).take_lines(4).collect(source(items(&[2, 2, 2, 2])));
+        assert_lines(&["1.0", "1.1", "2.0", "2.1"], &[1, 1, 2, 2], 2, false, lines);
+    }
+
+    #[test]
+    fn it_should_take_all_lines_when_screen_is_bigger() {
+        let lines = collector().take_lines(10).collect(source(items(&[1, 2])));
+        assert_lines(&["0.0", "1.0", "1.1"], &[0, 1, 1], 2, false, lines);
+    }
+
+    #[test]
+    fn it_should_take_last_lines_and_crop_first_item() {
+        let lines = collector().take_items(3).take_last_lines(3).collect(source(items(&[2, 2, 2, 2])));
+        assert_lines(&["1.1", "2.0", "2.1"], &[1, 2, 2], 2, true, lines);
+    }
+
+    #[test]
+    fn it_should_not_count_skipped_items_when_last_lines_fit_exactly() {
+        let lines = collector().take_items(3).take_last_lines(4).collect(source(items(&[2, 2, 2])));
+        assert_lines(&["1.0", "1.1", "2.0", "2.1"], &[1, 1, 2, 2], 2, false, lines);
+    }
+
+    #[test]
+    fn it_should_not_crop_items_when_last_lines_are_not_enough() {
+        let lines = collector().take_items(2).take_last_lines(5).collect(source(items(&[1, 1, 1])));
+        assert_lines(&["0.0", "1.0"], &[0, 1], 2, false, lines);
+    }
+
+    #[test]
+    fn it_should_count_wrapped_rows_as_lines() {
+        let lines = collector().skip_items(1).take_lines(3).collect(source(wrapped_items(&[1, 1, 1], 2)));
+        assert_lines(&["1.", "0", "2."], &[1, 1, 2], 2, true, lines);
+    }
+
+    #[test]
+    fn it_should_take_last_wrapped_rows() {
+        let lines = collector().take_items(2).take_last_lines(3).collect(source(wrapped_items(&[1, 1], 2)));
+        assert_lines(&["0", "1.", "0"], &[0, 1, 1], 2, true, lines);
+    }
+
+    #[test]
+    fn it_should_take_last_lines_of_item_bigger_than_screen() {
+        let lines = collector().take_items(2).take_last_lines(2).collect(source(items(&[1, 4])));
+        assert_lines(&["1.2", "1.3"], &[1, 1], 1, true, lines);
+    }
+
+    #[test]
+    fn it_should_request_only_visible_items() {
+        let requested = Cell::new(0);
+        let all_items = items(&[2; 100]);
+        let mut source = source(all_items);
+        let lines = collector().take_items(90).take_last_lines(3).collect(|index| {
+            requested.set(requested.get() + 1);
+            source(index)
+        });
+        assert_lines(&["88.1", "89.0", "89.1"], &[88, 89, 89], 2, true, lines);
+        assert_eq!(2, requested.get());
+    }
+}
@@ -0,0 +1,146 @@
+//! Command line of the pager, opened with `:`
+
+use super::{SearchPrompt, ExportFormat, ExportScope};
+
+pub enum Prompt {
+    Search(SearchPrompt),
+    Command(String)
+}
+
+impl Prompt {
+    pub fn input(&self) -> &str {
+        match self {
+            Prompt::Search(prompt) => &prompt.input,
+            Prompt::Command(input) => input
+        }
+    }
+
+    pub fn input_mut(&mut self) -> &mut String {
+        match self {
+            Prompt::Search(prompt) => &mut prompt.input,
+            Prompt::Command(input) => input
+        }
+    }
+
+    pub fn render(&self) -> String {
+        match self {
+            Prompt::Search(prompt) => prompt.render(),
+            Prompt::Command(input) => format!(":{}", input)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PagerCommand {
+    GoTo(usize),
+    Query(String),
+    Index(String),
+    /// Bounds of the time range, the range is removed when both are empty
+    Range(Option<String>, Option<String>),
+    Back,
+    Write { path: String, format: Option<ExportFormat>, scope: Option<ExportScope> },
+    Yank { format: Option<ExportFormat>, scope: Option<ExportScope> }
+}
+
+impl PagerCommand {
+    pub fn parse(input: &str) -> Result<PagerCommand, String> {
+        let input = input.trim();
+        let (name, argument) = match input.find(char::is_whitespace) {
+            Some(position) => (&input[..position], input[position..].trim()),
+            None => (input, "")
+        };
+        match name {
+            "query" if argument.is_empty() => Err("Query must be specified".to_string()),
+            "query" => Ok(PagerCommand::Query(argument.to_string())),
+            "index" if argument.is_empty() => Err("Index must be specified".to_string()),
+            "index" => Ok(PagerCommand::Index(argument.to_string())),
+            "range" => Self::parse_range(argument),
+            "back" => Ok(PagerCommand::Back),
+            "w" | "write" => {
+                let mut arguments = argument.split_whitespace();
+                let path = arguments.next()
+                    .ok_or_else(|| "File must be specified".to_string())?
+                    .to_string();
+                let (format, scope) = Self::parse_export_options(arguments)?;
+                Ok(PagerCommand::Write { path, format, scope })
+            },
+            "y" | "yank" => {
+                let (format, scope) = Self::parse_export_options(argument.split_whitespace())?;
+                Ok(PagerCommand::Yank { format, scope })
+            },
+            _ => input.parse::<usize>()
+                .map(PagerCommand::GoTo)
+                .map_err(|_| format!("Unknown command: {}", input))
+        }
+    }
+
+    /// `-` stands for the open bound, e.g. `range - now-1d`
+    fn parse_range(argument: &str) -> Result<PagerCommand, String> {
+        let bound = |value: Option<&str>| value
+            .filter(|value| *value != "-")
+            .map(ToString::to_string);
+        let bounds: Vec<&str> = argument.split_whitespace().collect();
+        if bounds.len() > 2 {
+            return Err("Range accepts only the start and the end".to_string());
+        }
+        Ok(PagerCommand::Range(bound(bounds.first().cloned()), bound(bounds.get(1).cloned())))
+    }
+
+    /// Format and scope of the exported items, in any order, e.g. `ndjson all`
+    fn parse_export_options<'a>(options: impl Iterator<Item = &'a str>) -> Result<(Option<ExportFormat>, Option<ExportScope>), String> {
+        let mut format = None;
+        let mut scope = None;
+        for option in options {
+            match (ExportFormat::parse(option), ExportScope::parse(option)) {
+                (Some(f), _) => format = Some(f),
+                (_, Some(s)) => scope = Some(s),
+                _ => return Err(format!("Unknown option: {}, expected json, ndjson, text, marked, current or all", option))
+            }
+        }
+        Ok((format, scope))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PagerCommand, ExportFormat, ExportScope};
+
+    #[test]
+    fn it_should_parse_item_number() {
+        assert_eq!(Ok(PagerCommand::GoTo(42)), PagerCommand::parse(" 42"));
+    }
+
+    #[test]
+    fn it_should_parse_query_and_index() {
+        assert_eq!(Ok(PagerCommand::Query("level: Error".to_string())), PagerCommand::parse("query  level: Error "));
+        assert_eq!(Ok(PagerCommand::Index("logs-*".to_string())), PagerCommand::parse("index logs-*"));
+        assert_eq!(Err("Query must be specified".to_string()), PagerCommand::parse("query"));
+    }
+
+    #[test]
+    fn it_should_parse_range() {
+        assert_eq!(Ok(PagerCommand::Range(Some("now-1h".to_string()), None)), PagerCommand::parse("range now-1h"));
+        assert_eq!(Ok(PagerCommand::Range(None, Some("now".to_string()))), PagerCommand::parse("range - now"));
+        assert_eq!(Ok(PagerCommand::Range(None, None)), PagerCommand::parse("range"));
+        assert!(PagerCommand::parse("range a b c").is_err());
+    }
+
+    #[test]
+    fn it_should_parse_write_and_yank() {
+        assert_eq!(
+            Ok(PagerCommand::Write { path: "out.log".to_string(), format: Some(ExportFormat::NDJSON), scope: Some(ExportScope::All) }),
+            PagerCommand::parse("w out.log all ndjson")
+        );
+        assert_eq!(
+            Ok(PagerCommand::Yank { format: None, scope: Some(ExportScope::Current) }),
+            PagerCommand::parse("yank current")
+        );
+        assert_eq!(Err("File must be specified".to_string()), PagerCommand::parse("write"));
+        assert!(PagerCommand::parse("yank xml").is_err());
+    }
+
+    #[test]
+    fn it_should_fail_on_unknown_command() {
+        assert_eq!(Err("Unknown command: abc".to_string()), PagerCommand::parse("abc"));
+    }
+}
@@ -0,0 +1,345 @@
+//! Scrollable view of a single document with all its fields
+
+use crate::display::{Formatter, TreeRow};
+use super::WrapMode;
+use super::text::wrap_line;
+
+use serde_json::Value;
+use colored::*;
+
+use std::cmp::{max, min};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub struct DetailView {
+    pub item_index: usize,
+    /// Lines laid out for the screen, wrapped if necessary
+    pub rows: Vec<String>,
+    pub offset: usize,
+    lines: Vec<String>,
+    /// Index of the line for each row
+    row_lines: Vec<usize>,
+    /// Width and wrap mode of the last layout
+    layout: Option<(usize, WrapMode)>,
+    tree: Option<TreeState>
+}
+
+/// Item shown as a tree with the cursor on one of its nodes
+struct TreeState {
+    item: Value,
+    formatter: Arc<Formatter>,
+    collapsed: BTreeSet<String>,
+    nodes: Vec<TreeRow>,
+    cursor: usize
+}
+
+impl DetailView {
+    pub fn new(item: &Value, item_index: usize) -> Self {
+        let lines: Vec<String> = colorize_json(item).lines().map(ToString::to_string).collect();
+        Self::with_lines(lines, item_index, None)
+    }
+
+    /// Shows the item in the tree format of the formatter, nodes can be expanded and collapsed
+    pub fn tree(item: &Value, item_index: usize, formatter: Arc<Formatter>) -> Self {
+        let nodes = formatter.format_tree(item, &BTreeSet::new());
+        let lines = nodes.iter().map(|node| node.line.clone()).collect();
+        let tree = TreeState {
+            item: item.clone(),
+            formatter,
+            collapsed: BTreeSet::new(),
+            nodes,
+            cursor: 0
+        };
+        Self::with_lines(lines, item_index, Some(tree))
+    }
+
+    fn with_lines(lines: Vec<String>, item_index: usize, tree: Option<TreeState>) -> Self {
+        DetailView {
+            item_index,
+            rows: lines.clone(),
+            offset: 0,
+            row_lines: (0..lines.len()).collect(),
+            lines,
+            layout: None,
+            tree
+        }
+    }
+
+    pub fn is_tree(&self) -> bool {
+        self.tree.is_some()
+    }
+
+    pub fn layout(&mut self, width: usize, wrap_mode: WrapMode) {
+        let (rows, row_lines) = self.lines.iter()
+            .enumerate()
+            .flat_map(|(line_index, line)| {
+                let rows = match wrap_mode {
+                    WrapMode::Wrap => wrap_line(line, width),
+                    WrapMode::NoWrap => vec![line.clone()]
+                };
+                rows.into_iter().map(move |row| (row, line_index))
+            })
+            .unzip();
+        self.rows = rows;
+        self.row_lines = row_lines;
+        self.layout = Some((width, wrap_mode));
+        self.offset = min(self.offset, max(self.rows.len(), 1) - 1);
+    }
+
+    pub fn scroll_up(&mut self, n: usize) {
+        self.offset -= min(self.offset, n);
+    }
+
+    pub fn scroll_down(&mut self, n: usize, height: usize) {
+        self.offset = min(self.offset + n, self.max_offset(height));
+    }
+
+    pub fn scroll_to_end(&mut self, height: usize) {
+        self.offset = self.max_offset(height);
+    }
+
+    pub fn visible_rows(&self, height: usize) -> &[String] {
+        let to = min(self.offset + height, self.rows.len());
+        &self.rows[self.offset..to]
+    }
+
+    /// The row belongs to the node under the cursor of the tree
+    pub fn is_cursor_row(&self, row: usize) -> bool {
+        match (&self.tree, self.row_lines.get(row)) {
+            (Some(tree), Some(&line)) => line == tree.cursor,
+            _ => false
+        }
+    }
+
+    /// Path of the node under the cursor of the tree
+    pub fn cursor_path(&self) -> Option<&str> {
+        self.tree.as_ref()
+            .and_then(|tree| tree.nodes.get(tree.cursor))
+            .map(|node| node.path.as_str())
+    }
+
+    pub fn move_cursor_up(&mut self, height: usize) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.cursor = max(tree.cursor, 1) - 1;
+        }
+        self.reveal_cursor(height);
+    }
+
+    pub fn move_cursor_down(&mut self, height: usize) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.cursor = min(tree.cursor + 1, max(tree.nodes.len(), 1) - 1);
+        }
+        self.reveal_cursor(height);
+    }
+
+    /// Expands or collapses the node under the cursor
+    pub fn toggle_node(&mut self, height: usize) {
+        if let Some(tree) = self.tree.as_mut() {
+            let node = match tree.nodes.get(tree.cursor) {
+                Some(node) if node.is_container => node.path.clone(),
+                _ => return
+            };
+            if !tree.collapsed.remove(&node) {
+                tree.collapsed.insert(node);
+            }
+        }
+        self.rebuild(height);
+    }
+
+    pub fn expand_all(&mut self, height: usize) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.collapsed.clear();
+        }
+        self.rebuild(height);
+    }
+
+    pub fn collapse_all(&mut self, height: usize) {
+        if let Some(tree) = self.tree.as_mut() {
+            let nodes = tree.formatter.format_tree(&tree.item, &BTreeSet::new());
+            tree.collapsed = nodes.into_iter()
+                .filter(|node| node.is_container)
+                .map(|node| node.path)
+                .collect();
+        }
+        self.rebuild(height);
+    }
+
+    /// Renders the tree again keeping the cursor on the same node or on its visible ancestor
+    fn rebuild(&mut self, height: usize) {
+        let tree = match self.tree.as_mut() {
+            Some(tree) => tree,
+            None => return
+        };
+        let cursor_path = tree.nodes.get(tree.cursor).map(|node| node.path.clone()).unwrap_or_default();
+        tree.nodes = tree.formatter.format_tree(&tree.item, &tree.collapsed);
+        tree.cursor = tree.nodes.iter()
+            .enumerate()
+            .filter(|(_, node)| node.path == cursor_path || cursor_path.starts_with(&format!("{}.", node.path)))
+            .max_by_key(|(_, node)| node.path.len())
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        self.lines = tree.nodes.iter().map(|node| node.line.clone()).collect();
+        match self.layout {
+            Some((width, wrap_mode)) => self.layout(width, wrap_mode),
+            None => self.layout(usize::MAX, WrapMode::NoWrap)
+        }
+        self.reveal_cursor(height);
+    }
+
+    /// Scrolls to the first row of the node under the cursor if it is not visible
+    fn reveal_cursor(&mut self, height: usize) {
+        let cursor = match &self.tree {
+            Some(tree) => tree.cursor,
+            None => return
+        };
+        if let Some(first_row) = self.row_lines.iter().position(|&line| line == cursor) {
+            if first_row < self.offset {
+                self.offset = first_row;
+            } else if first_row >= self.offset + height {
+                self.offset = first_row + 1 - height;
+            }
+        }
+    }
+
+    fn max_offset(&self, height: usize) -> usize {
+        self.rows.len() - min(self.rows.len(), height)
+    }
+}
+
+/// Pretty prints json with the syntax highlighting
+pub fn colorize_json(value: &Value) -> String {
+    let mut str = String::new();
+    write_value(&mut str, value, 0);
+    str
+}
+
+fn write_value(str: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            str.push_str("{\n");
+            for (position, (key, value)) in object.iter().enumerate() {
+                let _ = write!(str, "{}{}: ", " ".repeat(indent + 2), Value::String(key.clone()).to_string().blue().bold());
+                write_value(str, value, indent + 2);
+                str.push_str(if position + 1 < object.len() { ",\n" } else { "\n" });
+            }
+            let _ = write!(str, "{}}}", " ".repeat(indent));
+        },
+        Value::Array(array) if !array.is_empty() => {
+            str.push_str("[\n");
+            for (position, value) in array.iter().enumerate() {
+                str.push_str(&" ".repeat(indent + 2));
+                write_value(str, value, indent + 2);
+                str.push_str(if position + 1 < array.len() { ",\n" } else { "\n" });
+            }
+            let _ = write!(str, "{}]", " ".repeat(indent));
+        },
+        Value::String(_) => { let _ = write!(str, "{}", value.to_string().green()); },
+        Value::Number(_) => { let _ = write!(str, "{}", value.to_string().cyan()); },
+        Value::Bool(_) => { let _ = write!(str, "{}", value.to_string().yellow()); },
+        Value::Null => { let _ = write!(str, "{}", "null".magenta()); },
+        empty => { let _ = write!(str, "{}", empty); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::{OutputFormat, JSONExtractor};
+
+    #[test]
+    fn colorize_json_should_pretty_print_nested_values() {
+        let str = colorize_json(&json!({
+            "obj": { "arr": [1, null] },
+            "empty": {}
+        }));
+
+        assert_eq!(
+            format!(
+                "{{\n  {}: {{}},\n  {}: {{\n    {}: [\n      {},\n      {}\n    ]\n  }}\n}}",
+                "\"empty\"".blue().bold(),
+                "\"obj\"".blue().bold(),
+                "\"arr\"".blue().bold(),
+                "1".cyan(),
+                "null".magenta()
+            ),
+            str
+        );
+    }
+
+    #[test]
+    fn detail_view_should_not_scroll_beyond_content() {
+        let mut view = DetailView::new(&json!({ "a": 1, "b": 2, "c": 3 }), 0);
+        assert_eq!(5, view.rows.len());
+
+        view.scroll_down(10, 3);
+        assert_eq!(2, view.offset);
+        assert_eq!(3, view.visible_rows(3).len());
+
+        view.scroll_up(1);
+        assert_eq!(1, view.offset);
+        view.scroll_up(10);
+        assert_eq!(0, view.offset);
+    }
+
+    fn tree_view() -> DetailView {
+        let formatter = Arc::new(Formatter::new(OutputFormat::Tree, JSONExtractor::default()));
+        DetailView::tree(&json!({ "a": { "b": { "c": 1 }, "d": 2 }, "e": 3 }), 0, formatter)
+    }
+
+    #[test]
+    fn tree_view_should_collapse_node_under_cursor() {
+        let mut view = tree_view();
+        assert_eq!(5, view.rows.len());
+
+        view.move_cursor_down(10);
+        assert_eq!(Some("a.b"), view.cursor_path());
+        view.toggle_node(10);
+        assert_eq!(4, view.rows.len());
+        assert!(view.is_cursor_row(1));
+
+        view.toggle_node(10);
+        assert_eq!(5, view.rows.len());
+    }
+
+    #[test]
+    fn tree_view_should_keep_cursor_on_visible_ancestor() {
+        let mut view = tree_view();
+        view.move_cursor_down(10);
+        view.move_cursor_down(10);
+        assert_eq!(Some("a.b.c"), view.cursor_path());
+
+        view.collapse_all(10);
+        assert_eq!(2, view.rows.len());
+        assert_eq!(Some("a"), view.cursor_path());
+
+        view.expand_all(10);
+        assert_eq!(5, view.rows.len());
+        assert_eq!(Some("a"), view.cursor_path());
+    }
+
+    #[test]
+    fn tree_view_should_scroll_to_cursor() {
+        let mut view = tree_view();
+        for _ in 0..4 {
+            view.move_cursor_down(2);
+        }
+        assert_eq!(Some("e"), view.cursor_path());
+        assert_eq!(3, view.offset);
+
+        view.move_cursor_up(2);
+        view.move_cursor_up(2);
+        view.move_cursor_up(2);
+        assert_eq!(1, view.offset);
+    }
+
+    #[test]
+    fn detail_view_should_wrap_long_lines() {
+        let mut view = DetailView::new(&json!({ "a": 1 }), 0);
+        view.layout(4, WrapMode::Wrap);
+        assert_eq!(4, view.rows.len());
+
+        view.layout(4, WrapMode::NoWrap);
+        assert_eq!(3, view.rows.len());
+    }
+}
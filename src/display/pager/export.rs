@@ -0,0 +1,118 @@
+//! Writing items from the pager to a file or to the clipboard
+
+use crate::display::Formatter;
+use super::text::strip_ansi;
+
+use serde_json::Value;
+
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    JSON,
+    NDJSON,
+    /// The active output format of the pager
+    Text
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(ExportFormat::JSON),
+            "ndjson" => Some(ExportFormat::NDJSON),
+            "text" => Some(ExportFormat::Text),
+            _ => None
+        }
+    }
+
+    /// Guesses the format by the file extension
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some("json") => ExportFormat::JSON,
+            Some("ndjson") | Some("jsonl") => ExportFormat::NDJSON,
+            _ => ExportFormat::Text
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportScope {
+    Marked,
+    Current,
+    All
+}
+
+impl ExportScope {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "marked" => Some(ExportScope::Marked),
+            "current" => Some(ExportScope::Current),
+            "all" => Some(ExportScope::All),
+            _ => None
+        }
+    }
+}
+
+pub fn export_items(items: &[Value], format: ExportFormat, formatter: &Formatter) -> String {
+    match format {
+        ExportFormat::JSON => {
+            let array = Value::Array(items.to_vec());
+            format!("{}\n", serde_json::to_string_pretty(&array).unwrap_or_else(|_| array.to_string()))
+        },
+        ExportFormat::NDJSON => items.iter()
+            .map(|item| format!("{}\n", item))
+            .collect(),
+        ExportFormat::Text => items.iter()
+            .enumerate()
+            .map(|(index, item)| strip_ansi(&formatter.format(item, index)))
+            .collect()
+    }
+}
+
+/// Escape sequence which asks the terminal to put the text into the clipboard, works over SSH as well
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::encode(text))
+}
+
+/// Expands `~` at the beginning of the path to the home directory
+pub fn expand_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::{OutputFormat, JSONExtractor};
+
+    fn items() -> Vec<Value> {
+        vec![json!({ "a": 1 }), json!({ "a": 2 })]
+    }
+
+    #[test]
+    fn it_should_export_items_as_json_array_and_ndjson() {
+        let formatter = Formatter::new(OutputFormat::JSON, JSONExtractor::default());
+        assert_eq!("[\n  {\n    \"a\": 1\n  },\n  {\n    \"a\": 2\n  }\n]\n", export_items(&items(), ExportFormat::JSON, &formatter));
+        assert_eq!("{\"a\":1}\n{\"a\":2}\n", export_items(&items(), ExportFormat::NDJSON, &formatter));
+    }
+
+    #[test]
+    fn it_should_export_items_in_output_format_without_colors() {
+        let formatter = Formatter::new(OutputFormat::Pretty, JSONExtractor::default());
+        assert_eq!("a: 1\n----\na: 2\n", export_items(&items(), ExportFormat::Text, &formatter));
+    }
+
+    #[test]
+    fn it_should_guess_format_by_extension() {
+        assert_eq!(ExportFormat::JSON, ExportFormat::from_path("items.json"));
+        assert_eq!(ExportFormat::NDJSON, ExportFormat::from_path("/tmp/items.jsonl"));
+        assert_eq!(ExportFormat::Text, ExportFormat::from_path("items.log"));
+    }
+
+    #[test]
+    fn osc52_should_encode_text() {
+        assert_eq!("\x1b]52;c;YWJj\x07", osc52("abc"));
+    }
+}
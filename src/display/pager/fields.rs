@@ -0,0 +1,172 @@
+//! Overlay for choosing the displayed fields
+
+use crate::display::JSONExtractor;
+
+use serde_json::Value;
+
+use std::cmp::max;
+use std::collections::BTreeMap;
+
+/// How many of the loaded items contain each field
+pub struct FieldStats {
+    extractor: JSONExtractor,
+    pub counts: BTreeMap<String, usize>,
+    pub scanned: usize
+}
+
+impl FieldStats {
+    pub fn new() -> Self {
+        FieldStats {
+            extractor: JSONExtractor::default(),
+            counts: BTreeMap::new(),
+            scanned: 0
+        }
+    }
+
+    pub fn add_item(&mut self, index: usize, item: &Value) {
+        if index < self.scanned {
+            return;
+        }
+        for key in self.extractor.extract(item).into_keys() {
+            *self.counts.entry(key).or_insert(0) += 1;
+        }
+        self.scanned = index + 1;
+    }
+}
+
+pub struct FieldEntry {
+    pub name: String,
+    pub count: usize,
+    pub selected: bool
+}
+
+pub struct FieldPicker {
+    pub entries: Vec<FieldEntry>,
+    pub cursor: usize
+}
+
+impl FieldPicker {
+    /// Selected fields go first in their order, the rest of the seen fields are sorted by name
+    pub fn new(selected: &[String], stats: &FieldStats) -> Self {
+        let count = |name: &str| stats.counts.get(name).cloned().unwrap_or(0);
+        let mut entries: Vec<FieldEntry> = selected.iter()
+            .map(|name| FieldEntry { name: name.clone(), count: count(name), selected: true })
+            .collect();
+        entries.extend(
+            stats.counts.iter()
+                .filter(|(name, _)| !selected.contains(name))
+                .map(|(name, &count)| FieldEntry { name: name.clone(), count, selected: false })
+        );
+        FieldPicker {
+            entries,
+            cursor: 0
+        }
+    }
+
+    pub fn move_cursor_up(&mut self) {
+        self.cursor = max(self.cursor, 1) - 1;
+    }
+
+    pub fn move_cursor_down(&mut self) {
+        if self.cursor + 1 < self.entries.len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(entry) = self.entries.get_mut(self.cursor) {
+            entry.selected = !entry.selected;
+        }
+    }
+
+    /// Moves the field under the cursor one position up, the cursor follows it
+    pub fn move_field_up(&mut self) {
+        if self.cursor > 0 && self.cursor < self.entries.len() {
+            self.entries.swap(self.cursor - 1, self.cursor);
+            self.cursor -= 1;
+        }
+    }
+
+    /// Moves the field under the cursor one position down, the cursor follows it
+    pub fn move_field_down(&mut self) {
+        if self.cursor + 1 < self.entries.len() {
+            self.entries.swap(self.cursor, self.cursor + 1);
+            self.cursor += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| entry.selected = false);
+    }
+
+    pub fn selected_fields(&self) -> Vec<String> {
+        self.entries.iter()
+            .filter(|entry| entry.selected)
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+}
+
+/// The `-f` argument selecting the fields, quoted when the shell may split it
+pub fn fields_argument(fields: &[String]) -> String {
+    let value = fields.join(",");
+    let is_safe = value.chars().all(|c| c.is_alphanumeric() || "_-.,@".contains(c));
+    if is_safe {
+        format!("-f {}", value)
+    } else {
+        format!("-f '{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> FieldStats {
+        let mut stats = FieldStats::new();
+        stats.add_item(0, &json!({ "a": 1, "b": { "c": 2 } }));
+        stats.add_item(1, &json!({ "a": 3 }));
+        // already scanned items are not counted twice
+        stats.add_item(1, &json!({ "a": 3 }));
+        stats
+    }
+
+    #[test]
+    fn field_stats_should_count_items_with_each_field() {
+        let stats = stats();
+        assert_eq!(Some(&2), stats.counts.get("a"));
+        assert_eq!(Some(&1), stats.counts.get("b.c"));
+        assert_eq!(2, stats.scanned);
+    }
+
+    #[test]
+    fn field_picker_should_list_selected_fields_first() {
+        let picker = FieldPicker::new(&["b.c".to_string(), "x".to_string()], &stats());
+        let entries: Vec<(&str, usize, bool)> = picker.entries.iter()
+            .map(|entry| (entry.name.as_str(), entry.count, entry.selected))
+            .collect();
+        assert_eq!(vec![("b.c", 1, true), ("x", 0, true), ("a", 2, false)], entries);
+    }
+
+    #[test]
+    fn field_picker_should_toggle_and_reorder_fields() {
+        let mut picker = FieldPicker::new(&[], &stats());
+        picker.toggle();
+        picker.move_cursor_down();
+        picker.toggle();
+        assert_eq!(vec!["a", "b.c"], picker.selected_fields());
+
+        picker.move_field_up();
+        assert_eq!(0, picker.cursor);
+        assert_eq!(vec!["b.c", "a"], picker.selected_fields());
+
+        picker.clear();
+        assert!(picker.selected_fields().is_empty());
+    }
+
+    #[test]
+    fn fields_argument_should_quote_unsafe_names() {
+        assert_eq!("-f a.b,c", fields_argument(&["a.b".to_string(), "c".to_string()]));
+        assert_eq!("-f 'a b,it'\\''s'", fields_argument(&["a b".to_string(), "it's".to_string()]));
+    }
+}
@@ -0,0 +1,45 @@
+//! Help overlay with the active key bindings of the pager
+
+use super::{Action, Keymap, key_name};
+
+/// Keys and commands which are not configurable
+const FIXED_BINDINGS: &[(&str, &str)] = &[
+    ("Ctrl-R", "toggle regex in the search prompt"),
+    ("Ctrl-T", "toggle case-insensitive mode in the search prompt"),
+    ("Tab", "switch between the query, the index and the time range while editing the search"),
+    (":N", "go to the item N"),
+    (":query <query>", "search with another query"),
+    (":index <index>", "search in another index"),
+    (":range <from> <to>", "search in another time range, - for the open bound"),
+    (":back", "return to the previous search"),
+    (":w <file>", "save the marked or selected items, options: json, ndjson, text, marked, current, all"),
+    (":y", "copy items to the clipboard, options are the same as for :w")
+];
+
+pub fn help_lines(keymap: &Keymap) -> Vec<String> {
+    let bindings: Vec<(String, &str)> = Action::ALL.iter()
+        .map(|&action| {
+            let keys: Vec<String> = keymap.keys(action).into_iter().map(key_name).collect();
+            let keys = if keys.is_empty() { "-".to_string() } else { keys.join(", ") };
+            (keys, action.description())
+        })
+        .chain(FIXED_BINDINGS.iter().map(|&(keys, description)| (keys.to_string(), description)))
+        .collect();
+
+    let keys_width = bindings.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+    bindings.iter()
+        .map(|(keys, description)| format!("{:>width$}  {}", keys, description, width = keys_width))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_should_show_active_keys() {
+        let lines = help_lines(&Keymap::default());
+        assert_eq!(Action::ALL.len() + FIXED_BINDINGS.len(), lines.len());
+        assert!(lines.iter().any(|line| line.trim_start() == "PgDn, Ctrl-F, Space  scroll down by the screen"));
+    }
+}
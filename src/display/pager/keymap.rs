@@ -0,0 +1,357 @@
+//! Actions of the pager and the keys bound to them
+
+use termion::event::Key;
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Quit,
+    Help,
+    CursorUp,
+    CursorDown,
+    PageUp,
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    First,
+    LastLoaded,
+    Last,
+    Command,
+    Open,
+    SearchForward,
+    SearchBackward,
+    NextMatch,
+    PreviousMatch,
+    ToggleWrap,
+    ScrollLeft,
+    ScrollRight,
+    Fields,
+    EditSearch,
+    Back,
+    Mark,
+    Yank,
+    Retry,
+    Follow,
+    ExpandAll,
+    CollapseAll,
+    Close,
+    MoveFieldUp,
+    MoveFieldDown,
+    ClearFields
+}
+
+impl Action {
+    pub const ALL: [Action; 33] = [
+        Action::Quit, Action::Help, Action::CursorUp, Action::CursorDown, Action::PageUp, Action::PageDown,
+        Action::HalfPageUp, Action::HalfPageDown, Action::First, Action::LastLoaded, Action::Last,
+        Action::Command, Action::Open, Action::SearchForward, Action::SearchBackward, Action::NextMatch,
+        Action::PreviousMatch, Action::ToggleWrap, Action::ScrollLeft, Action::ScrollRight, Action::Fields,
+        Action::EditSearch, Action::Back, Action::Mark, Action::Yank, Action::Retry,
+        Action::Follow, Action::ExpandAll, Action::CollapseAll, Action::Close, Action::MoveFieldUp,
+        Action::MoveFieldDown, Action::ClearFields
+    ];
+
+    /// Name of the action in the configuration file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::CursorUp => "cursor_up",
+            Action::CursorDown => "cursor_down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::HalfPageUp => "half_page_up",
+            Action::HalfPageDown => "half_page_down",
+            Action::First => "first",
+            Action::LastLoaded => "last_loaded",
+            Action::Last => "last",
+            Action::Command => "command",
+            Action::Open => "open",
+            Action::SearchForward => "search_forward",
+            Action::SearchBackward => "search_backward",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::ToggleWrap => "toggle_wrap",
+            Action::ScrollLeft => "scroll_left",
+            Action::ScrollRight => "scroll_right",
+            Action::Fields => "fields",
+            Action::EditSearch => "edit_search",
+            Action::Back => "back",
+            Action::Mark => "mark",
+            Action::Yank => "yank",
+            Action::Retry => "retry",
+            Action::Follow => "follow",
+            Action::ExpandAll => "expand_all",
+            Action::CollapseAll => "collapse_all",
+            Action::Close => "close",
+            Action::MoveFieldUp => "move_field_up",
+            Action::MoveFieldDown => "move_field_down",
+            Action::ClearFields => "clear_fields"
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "exit",
+            Action::Help => "show this help",
+            Action::CursorUp => "move the cursor up",
+            Action::CursorDown => "move the cursor down",
+            Action::PageUp => "scroll up by the screen",
+            Action::PageDown => "scroll down by the screen",
+            Action::HalfPageUp => "scroll up by half of the screen",
+            Action::HalfPageDown => "scroll down by half of the screen",
+            Action::First => "go to the first item",
+            Action::LastLoaded => "go to the last loaded item",
+            Action::Last => "load all items and go to the last one",
+            Action::Command => "open the command prompt",
+            Action::Open => "open the selected item, expand or collapse the node in the tree format, toggle the field in the field picker",
+            Action::SearchForward => "search forward",
+            Action::SearchBackward => "search backward",
+            Action::NextMatch => "jump to the next match",
+            Action::PreviousMatch => "jump to the previous match",
+            Action::ToggleWrap => "toggle wrapping of long lines",
+            Action::ScrollLeft => "scroll left when wrapping is off",
+            Action::ScrollRight => "scroll right when wrapping is off",
+            Action::Fields => "pick the displayed fields",
+            Action::EditSearch => "edit the query, Tab switches to the index and the time range",
+            Action::Back => "return to the previous search",
+            Action::Mark => "mark or unmark the selected item, toggle the field in the field picker",
+            Action::Yank => "copy the marked or selected items to the clipboard",
+            Action::Retry => "retry loading after an error",
+            Action::Follow => "follow new items at the end, like tail -f",
+            Action::ExpandAll => "expand all nodes of the item opened in the tree format",
+            Action::CollapseAll => "collapse all nodes of the item opened in the tree format",
+            Action::Close => "close the opened item or the field picker",
+            Action::MoveFieldUp => "move the field up in the field picker",
+            Action::MoveFieldDown => "move the field down in the field picker",
+            Action::ClearFields => "show all fields, in the field picker"
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Action> {
+        Action::ALL.iter().cloned().find(|action| action.name() == name)
+    }
+
+    fn default_keys(self) -> Vec<Key> {
+        match self {
+            Action::Quit => vec![Key::Char('q'), Key::Ctrl('c')],
+            Action::Help => vec![Key::F(1)],
+            Action::CursorUp => vec![Key::Up, Key::Char('k')],
+            Action::CursorDown => vec![Key::Down, Key::Char('j')],
+            Action::PageUp => vec![Key::PageUp, Key::Ctrl('b')],
+            Action::PageDown => vec![Key::PageDown, Key::Ctrl('f'), Key::Char(' ')],
+            Action::HalfPageUp => vec![Key::Ctrl('u')],
+            Action::HalfPageDown => vec![Key::Ctrl('d')],
+            Action::First => vec![Key::Home, Key::Char('g')],
+            Action::LastLoaded => vec![Key::End],
+            Action::Last => vec![Key::Char('G')],
+            Action::Command => vec![Key::Char(':')],
+            Action::Open => vec![Key::Char('\n')],
+            Action::SearchForward => vec![Key::Char('/')],
+            Action::SearchBackward => vec![Key::Char('?')],
+            Action::NextMatch => vec![Key::Char('n')],
+            Action::PreviousMatch => vec![Key::Char('N')],
+            Action::ToggleWrap => vec![Key::Char('w')],
+            Action::ScrollLeft => vec![Key::Left, Key::Char('h')],
+            Action::ScrollRight => vec![Key::Right, Key::Char('l')],
+            Action::Fields => vec![Key::Char('f')],
+            Action::EditSearch => vec![Key::Char('e')],
+            Action::Back => vec![Key::Char('b')],
+            Action::Mark => vec![Key::Char('m')],
+            Action::Yank => vec![Key::Char('y')],
+            Action::Retry => vec![Key::Char('r')],
+            Action::Follow => vec![Key::Char('F')],
+            Action::ExpandAll => vec![Key::Char('+')],
+            Action::CollapseAll => vec![Key::Char('-')],
+            Action::Close => vec![Key::Esc, Key::Backspace],
+            Action::MoveFieldUp => vec![Key::Char('K')],
+            Action::MoveFieldDown => vec![Key::Char('J')],
+            Action::ClearFields => vec![Key::Char('c')]
+        }
+    }
+}
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum KeymapError {
+    #[fail(display = "unknown action '{}'", action)]
+    UnknownAction { action: String },
+    #[fail(display = "unknown key '{}' of the action '{}'", key, action)]
+    UnknownKey { key: String, action: String }
+}
+
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: Action::ALL.iter()
+                .flat_map(|&action| action.default_keys().into_iter().map(move |key| (key, action)))
+                .collect()
+        }
+    }
+}
+
+impl Keymap {
+    /// Replaces the default keys of the actions, keys are taken from the other actions
+    pub fn with_overrides(overrides: &BTreeMap<String, Vec<String>>) -> Result<Self, KeymapError> {
+        let mut keymap = Keymap::default();
+        for (name, keys) in overrides {
+            let action = Action::parse(name)
+                .ok_or_else(|| KeymapError::UnknownAction { action: name.clone() })?;
+            let keys = keys.iter()
+                .map(|key| parse_key(key).ok_or_else(|| KeymapError::UnknownKey { key: key.clone(), action: name.clone() }))
+                .collect::<Result<Vec<Key>, KeymapError>>()?;
+            keymap.bindings.retain(|(bound_key, bound_action)| *bound_action != action && !keys.contains(bound_key));
+            keymap.bindings.extend(keys.into_iter().map(|key| (key, action)));
+        }
+        Ok(keymap)
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings.iter()
+            .find(|(bound_key, _)| *bound_key == key)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings.iter()
+            .filter(|(_, bound_action)| *bound_action == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Hint for the bottom line, e.g. `q: exit`, empty if the action has no keys
+    pub fn hint(&self, action: Action, text: &str) -> Option<String> {
+        self.keys(action).first().map(|key| format!("{}: {}", key_name(*key), text))
+    }
+}
+
+/// Parses the key name from the configuration file, e.g. `j`, `G`, `ctrl-f`, `pgdown` or `f1`
+pub fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+    let lower = name.to_lowercase();
+    let single_char = |value: &str| {
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None
+        }
+    };
+    if let Some(rest) = lower.strip_prefix("ctrl-") {
+        return single_char(rest).map(Key::Ctrl);
+    }
+    if let Some(rest) = name.get(4..).filter(|_| lower.starts_with("alt-")) {
+        return single_char(rest).map(Key::Alt);
+    }
+    if let Some(number) = lower.strip_prefix('f').and_then(|rest| rest.parse::<u8>().ok()) {
+        return Some(Key::F(number)).filter(|_| (1..=12).contains(&number));
+    }
+    match lower.as_str() {
+        "up" => Some(Key::Up),
+        "down" => Some(Key::Down),
+        "left" => Some(Key::Left),
+        "right" => Some(Key::Right),
+        "pgup" | "pageup" => Some(Key::PageUp),
+        "pgdown" | "pagedown" => Some(Key::PageDown),
+        "home" => Some(Key::Home),
+        "end" => Some(Key::End),
+        "enter" => Some(Key::Char('\n')),
+        "tab" => Some(Key::Char('\t')),
+        "space" => Some(Key::Char(' ')),
+        "esc" => Some(Key::Esc),
+        "backspace" => Some(Key::Backspace),
+        "delete" => Some(Key::Delete),
+        "insert" => Some(Key::Insert),
+        _ => None
+    }
+}
+
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char('\n') => "Enter".to_string(),
+        Key::Char('\t') => "Tab".to_string(),
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{}", c.to_uppercase()),
+        Key::Alt(c) => format!("Alt-{}", c),
+        Key::F(number) => format!("F{}", number),
+        Key::Up => "↑".to_string(),
+        Key::Down => "↓".to_string(),
+        Key::Left => "←".to_string(),
+        Key::Right => "→".to_string(),
+        Key::PageUp => "PgUp".to_string(),
+        Key::PageDown => "PgDn".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::Esc => "Esc".to_string(),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Insert => "Insert".to_string(),
+        other => format!("{:?}", other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(bindings: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        bindings.iter()
+            .map(|(action, keys)| (action.to_string(), keys.iter().map(ToString::to_string).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn default_keymap_should_have_vim_and_less_keys() {
+        let keymap = Keymap::default();
+        assert_eq!(Some(Action::CursorDown), keymap.action(Key::Char('j')));
+        assert_eq!(Some(Action::PageDown), keymap.action(Key::Ctrl('f')));
+        assert_eq!(Some(Action::HalfPageUp), keymap.action(Key::Ctrl('u')));
+        assert_eq!(Some(Action::SearchBackward), keymap.action(Key::Char('?')));
+        assert_eq!(Some(Action::Help), keymap.action(Key::F(1)));
+        assert_eq!(Some(Action::CollapseAll), keymap.action(Key::Char('-')));
+        assert_eq!(Some(Action::Close), keymap.action(Key::Esc));
+        assert_eq!(Some(Action::MoveFieldDown), keymap.action(Key::Char('J')));
+        assert_eq!(None, keymap.action(Key::Char('z')));
+    }
+
+    #[test]
+    fn overrides_should_replace_keys_of_action() {
+        let keymap = Keymap::with_overrides(&overrides(&[("page_down", &["ctrl-v", "j"])])).unwrap();
+        assert_eq!(vec![Key::Ctrl('v'), Key::Char('j')], keymap.keys(Action::PageDown));
+        assert_eq!(None, keymap.action(Key::Ctrl('f')));
+        // the key is taken from the default action
+        assert_eq!(vec![Key::Down], keymap.keys(Action::CursorDown));
+    }
+
+    #[test]
+    fn overrides_should_fail_on_unknown_names() {
+        assert_eq!(
+            Err(KeymapError::UnknownAction { action: "jump".to_string() }),
+            Keymap::with_overrides(&overrides(&[("jump", &["j"])])).map(|_| ())
+        );
+        assert_eq!(
+            Err(KeymapError::UnknownKey { key: "ctrl-".to_string(), action: "quit".to_string() }),
+            Keymap::with_overrides(&overrides(&[("quit", &["ctrl-"])])).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn parse_key_should_support_named_keys() {
+        assert_eq!(Some(Key::Char('G')), parse_key("G"));
+        assert_eq!(Some(Key::Ctrl('d')), parse_key("Ctrl-D"));
+        assert_eq!(Some(Key::Alt('x')), parse_key("alt-x"));
+        assert_eq!(Some(Key::PageDown), parse_key("pgdown"));
+        assert_eq!(Some(Key::F(1)), parse_key("F1"));
+        assert_eq!(Some(Key::Char(' ')), parse_key("space"));
+        assert_eq!(None, parse_key("f13"));
+        assert_eq!(None, parse_key("hyper"));
+    }
+}
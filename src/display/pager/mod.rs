@@ -1,7 +1,27 @@
 mod ui;
 mod scroll_mode;
+mod wrap_mode;
 mod collector;
+mod cache;
+mod search;
+mod detail;
+mod command;
+mod fields;
+mod export;
+mod spinner;
+mod help;
+mod keymap;
+pub(crate) mod text;
 
 pub use self::ui::*;
 pub use self::scroll_mode::*;
-pub use self::collector::*;
+pub use self::wrap_mode::*;
+pub use self::cache::*;
+pub use self::search::*;
+pub use self::detail::*;
+pub use self::command::*;
+pub use self::fields::*;
+pub use self::export::*;
+pub use self::spinner::*;
+pub use self::help::*;
+pub use self::keymap::*;
@@ -0,0 +1,261 @@
+//! Less-style search inside the pager: patterns, prompt state and highlighting
+
+use super::text::{Segment, split_ansi, strip_ansi};
+
+use regex::{Regex, RegexBuilder};
+
+const HIGHLIGHT_START: &str = "\x1b[7m";
+const HIGHLIGHT_END: &str = "\x1b[27m";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchDirection {
+    Forward,
+    Backward
+}
+
+impl SearchDirection {
+    pub fn reversed(self) -> Self {
+        match self {
+            SearchDirection::Forward => SearchDirection::Backward,
+            SearchDirection::Backward => SearchDirection::Forward
+        }
+    }
+
+    pub fn prompt_char(self) -> char {
+        match self {
+            SearchDirection::Forward => '/',
+            SearchDirection::Backward => '?'
+        }
+    }
+}
+
+pub struct SearchPattern {
+    pub text: String,
+    pub is_regex: bool,
+    pub ignore_case: bool,
+    regex: Regex
+}
+
+impl SearchPattern {
+    pub fn new(text: &str, is_regex: bool, ignore_case: bool) -> Result<Self, regex::Error> {
+        let source = if is_regex { text.to_string() } else { regex::escape(text) };
+        RegexBuilder::new(&source)
+            .case_insensitive(ignore_case)
+            .build()
+            .map(|regex| SearchPattern {
+                text: text.to_string(),
+                is_regex,
+                ignore_case,
+                regex
+            })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(&strip_ansi(text))
+    }
+
+    /// Wraps every match in the line into the inverse video,
+    /// escape sequences of the original line are preserved
+    pub fn highlight(&self, line: &str) -> String {
+        let segments = split_ansi(line);
+        let plain: String = segments.iter()
+            .filter_map(|segment| match segment {
+                Segment::Char(c) => Some(*c),
+                Segment::Escape(_) => None
+            })
+            .collect();
+
+        let ranges: Vec<(usize, usize)> = self.regex.find_iter(&plain)
+            .filter(|m| m.start() != m.end())
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        if ranges.is_empty() {
+            return line.to_string();
+        }
+
+        let mut result = String::with_capacity(line.len() + ranges.len() * 10);
+        let mut offset = 0;
+        let mut ranges_iter = ranges.iter().peekable();
+        let mut inside = false;
+        for segment in segments {
+            match segment {
+                Segment::Escape(escape) => {
+                    result.push_str(escape);
+                    // escape sequence may reset the inverse attribute
+                    if inside {
+                        result.push_str(HIGHLIGHT_START);
+                    }
+                },
+                Segment::Char(c) => {
+                    if let Some(&&(start, end)) = ranges_iter.peek() {
+                        if !inside && offset == start {
+                            result.push_str(HIGHLIGHT_START);
+                            inside = true;
+                        }
+                        result.push(c);
+                        offset += c.len_utf8();
+                        if inside && offset == end {
+                            result.push_str(HIGHLIGHT_END);
+                            inside = false;
+                            ranges_iter.next();
+                        }
+                    } else {
+                        result.push(c);
+                        offset += c.len_utf8();
+                    }
+                }
+            }
+        }
+        if inside {
+            result.push_str(HIGHLIGHT_END);
+        }
+        result
+    }
+
+    pub fn describe(&self) -> String {
+        let mut flags = vec![];
+        if self.is_regex {
+            flags.push("regex");
+        }
+        if self.ignore_case {
+            flags.push("ignore case");
+        }
+        if flags.is_empty() {
+            self.text.clone()
+        } else {
+            format!("{} ({})", self.text, flags.join(", "))
+        }
+    }
+}
+
+/// Active search with the matching items found among the loaded ones
+pub struct Search {
+    pub pattern: SearchPattern,
+    pub direction: SearchDirection,
+    pub matches: Vec<usize>,
+    pub scanned: usize
+}
+
+impl Search {
+    pub fn new(pattern: SearchPattern, direction: SearchDirection) -> Self {
+        Search {
+            pattern,
+            direction,
+            matches: vec![],
+            scanned: 0
+        }
+    }
+
+    /// Forgets the found matches, e.g. when the items are formatted differently
+    pub fn reset(&mut self) {
+        self.matches.clear();
+        self.scanned = 0;
+    }
+
+    pub fn add_item(&mut self, index: usize, text: &str) {
+        if index < self.scanned {
+            return;
+        }
+        if self.pattern.is_match(text) {
+            self.matches.push(index);
+        }
+        self.scanned = index + 1;
+    }
+
+    /// Finds the nearest match after (or before) the item, `inclusive` allows the item itself
+    pub fn find(&self, item: usize, direction: SearchDirection, inclusive: bool) -> Option<usize> {
+        match direction {
+            SearchDirection::Forward => self.matches.iter()
+                .find(|&&m| m > item || (inclusive && m == item))
+                .cloned(),
+            SearchDirection::Backward => self.matches.iter()
+                .rev()
+                .find(|&&m| m < item || (inclusive && m == item))
+                .cloned()
+        }
+    }
+
+    /// 1-based position of the item among the matches
+    pub fn position(&self, item: usize) -> Option<usize> {
+        self.matches.binary_search(&item).ok().map(|p| p + 1)
+    }
+}
+
+/// Text typed into the search prompt
+pub struct SearchPrompt {
+    pub direction: SearchDirection,
+    pub input: String,
+    pub is_regex: bool,
+    pub ignore_case: bool
+}
+
+impl SearchPrompt {
+    pub fn new(direction: SearchDirection, is_regex: bool, ignore_case: bool) -> Self {
+        SearchPrompt {
+            direction,
+            input: String::new(),
+            is_regex,
+            ignore_case
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut flags = String::new();
+        if self.is_regex {
+            flags.push_str("[regex]");
+        }
+        if self.ignore_case {
+            flags.push_str("[ignore case]");
+        }
+        format!("{}{}{}", flags, self.direction.prompt_char(), self.input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_pattern_should_escape_regex_symbols() {
+        let pattern = SearchPattern::new("a.c", false, false).unwrap();
+        assert!(pattern.is_match("xa.cx"));
+        assert!(!pattern.is_match("abc"));
+    }
+
+    #[test]
+    fn regex_pattern_should_support_case_insensitive_mode() {
+        let pattern = SearchPattern::new("err(or)?", true, true).unwrap();
+        assert!(pattern.is_match("level: ERROR"));
+        assert!(!SearchPattern::new("err(or)?", true, false).unwrap().is_match("level: ERROR"));
+    }
+
+    #[test]
+    fn highlight_should_wrap_matches() {
+        let pattern = SearchPattern::new("b", false, false).unwrap();
+        assert_eq!("a\x1b[7mb\x1b[27mcа\x1b[7mb\x1b[27m", pattern.highlight("abcаb"));
+    }
+
+    #[test]
+    fn highlight_should_restore_inverse_after_escape_sequences() {
+        let pattern = SearchPattern::new("key: v", false, false).unwrap();
+        assert_eq!(
+            "\x1b[1;32m\x1b[7mkey\x1b[0m\x1b[7m: v\x1b[27malue",
+            pattern.highlight("\x1b[1;32mkey\x1b[0m: value")
+        );
+    }
+
+    #[test]
+    fn search_should_find_nearest_matches_in_both_directions() {
+        let mut search = Search::new(SearchPattern::new("x", false, false).unwrap(), SearchDirection::Forward);
+        ["x", "a", "x", "b", "x"].iter().enumerate()
+            .for_each(|(index, text)| search.add_item(index, text));
+
+        assert_eq!(vec![0, 2, 4], search.matches);
+        assert_eq!(Some(2), search.find(0, SearchDirection::Forward, false));
+        assert_eq!(Some(0), search.find(0, SearchDirection::Forward, true));
+        assert_eq!(Some(2), search.find(4, SearchDirection::Backward, false));
+        assert_eq!(None, search.find(4, SearchDirection::Forward, false));
+        assert_eq!(Some(3), search.position(4));
+        assert_eq!(None, search.position(3));
+    }
+}
@@ -0,0 +1,55 @@
+//! Animated indicator shown in the bottom line while the pager waits for the server
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const FRAME_DURATION: Duration = Duration::from_millis(100);
+
+/// The frames are drawn by the pager, the background thread only sends the ticks to its event loop
+/// until the spinner is dropped, so nothing else writes to the screen
+pub struct Spinner {
+    text: String,
+    started: Instant,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl Spinner {
+    pub fn start<E>(text: String, events: Sender<E>, tick: fn() -> E) -> Self where E: Send + 'static {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = stopped.clone();
+        let thread = thread::spawn(move || {
+            loop {
+                thread::sleep(FRAME_DURATION);
+                if thread_stopped.load(Ordering::SeqCst) || events.send(tick()).is_err() {
+                    break;
+                }
+            }
+        });
+        Spinner {
+            text,
+            started: Instant::now(),
+            stopped,
+            thread: Some(thread)
+        }
+    }
+
+    /// Current frame with the text, e.g. `⠙ Searching...`
+    pub fn render(&self) -> String {
+        let frame = (self.started.elapsed().as_millis() / FRAME_DURATION.as_millis()) as usize % FRAMES.len();
+        format!("{} {}", FRAMES[frame], self.text)
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Helpers for the terminal text with escape sequences: measuring, wrapping and cropping
+
+use unicode_width::UnicodeWidthChar;
+
+const RESET: &str = "\x1b[0m";
+
+pub enum Segment<'a> {
+    Escape(&'a str),
+    Char(char)
+}
+
+/// Splits the line into the escape sequences and printable characters
+pub fn split_ansi(line: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == '\x1b' && chars.peek().map(|&(_, n)| n == '[').unwrap_or(false) {
+            chars.next();
+            let mut end = line.len();
+            for (index, n) in chars.by_ref() {
+                if ('@'..='~').contains(&n) {
+                    end = index + n.len_utf8();
+                    break;
+                }
+            }
+            segments.push(Segment::Escape(&line[start..end]));
+        } else {
+            segments.push(Segment::Char(c));
+        }
+    }
+    segments
+}
+
+pub fn strip_ansi(text: &str) -> String {
+    split_ansi(text).into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Char(c) => Some(c),
+            Segment::Escape(_) => None
+        })
+        .collect()
+}
+
+/// Count of the terminal columns taken by the line
+pub fn display_width(line: &str) -> usize {
+    split_ansi(line).into_iter()
+        .map(|segment| match segment {
+            Segment::Char(c) => char_width(c),
+            Segment::Escape(_) => 0
+        })
+        .sum()
+}
+
+/// Splits the line into rows no wider than `width` columns,
+/// every row starts with the escape sequences active at its beginning and ends with the reset
+pub fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut rows = vec![];
+    let mut row = String::new();
+    let mut row_width = 0;
+    let mut active = String::new();
+    for segment in split_ansi(line) {
+        match segment {
+            Segment::Escape(escape) => {
+                if is_reset(escape) {
+                    active.clear();
+                } else {
+                    active.push_str(escape);
+                }
+                row.push_str(escape);
+            },
+            Segment::Char(c) => {
+                let c_width = char_width(c);
+                if row_width + c_width > width && row_width > 0 {
+                    if !active.is_empty() {
+                        row.push_str(RESET);
+                    }
+                    rows.push(row);
+                    row = active.clone();
+                    row_width = 0;
+                }
+                row.push(c);
+                row_width += c_width;
+            }
+        }
+    }
+    rows.push(row);
+    rows
+}
+
+/// Takes `width` columns of the line starting at the column `offset`,
+/// escape sequences of the skipped part are kept to preserve colors
+pub fn crop_line(line: &str, offset: usize, width: usize) -> String {
+    let mut result = String::new();
+    let mut column = 0;
+    let mut has_escapes = false;
+    for segment in split_ansi(line) {
+        match segment {
+            Segment::Escape(escape) => {
+                has_escapes = true;
+                result.push_str(escape);
+            },
+            Segment::Char(c) => {
+                let c_width = char_width(c);
+                if column >= offset && column + c_width <= offset + width {
+                    result.push(c);
+                }
+                column += c_width;
+            }
+        }
+    }
+    if has_escapes {
+        result.push_str(RESET);
+    }
+    result
+}
+
+fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+fn is_reset(escape: &str) -> bool {
+    escape == "\x1b[0m" || escape == "\x1b[m"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_ansi_should_remove_escape_sequences() {
+        assert_eq!("key: value", strip_ansi("\x1b[1;32mkey\x1b[0m: value"));
+    }
+
+    #[test]
+    fn display_width_should_ignore_escapes_and_count_wide_chars() {
+        assert_eq!(7, display_width("\x1b[1;32mkey\x1b[0m: 日"));
+    }
+
+    #[test]
+    fn wrap_line_should_split_line_by_width() {
+        assert_eq!(vec!["abc", "def", "g"], wrap_line("abcdefg", 3));
+        assert_eq!(vec![""], wrap_line("", 3));
+        assert_eq!(vec!["ab", "日c"], wrap_line("ab日c", 3));
+    }
+
+    #[test]
+    fn wrap_line_should_continue_colors_on_the_next_row() {
+        assert_eq!(
+            vec!["\x1b[32mab\x1b[0m", "\x1b[32mc\x1b[0md"],
+            wrap_line("\x1b[32mabc\x1b[0md", 2)
+        );
+    }
+
+    #[test]
+    fn crop_line_should_take_visible_columns() {
+        assert_eq!("cde", crop_line("abcdefg", 2, 3));
+        assert_eq!("", crop_line("abc", 5, 3));
+        assert_eq!("\x1b[32mc\x1b[0md\x1b[0m", crop_line("\x1b[32mabc\x1b[0mdef", 2, 2));
+    }
+}
@@ -1,76 +1,1010 @@
-use crate::client::Collector;
-use crate::display::Formatter;
-use super::ScrollMode;
+use crate::client::{Client, Collector, Page, SearchRequest, TimeRange, TotalRelation, DEFAULT_TIME_FIELD};
+use crate::display::{Formatter, JSONExtractor, RenderError};
+use super::{ScrollMode, WrapMode, Search, SearchDirection, SearchPattern, SearchPrompt, DetailView, Prompt, PagerCommand};
+use super::{FieldStats, FieldPicker, fields_argument, LinesCache};
+use super::{ExportFormat, ExportScope, export_items, expand_path, osc52, Spinner, help_lines, Action, Keymap};
 
 use serde_json::Value;
+use colored::*;
 use termion::event::{Key, Event, MouseEvent, MouseButton};
 use termion::input::TermRead;
 use termion::raw::{IntoRawMode, RawTerminal};
 use termion::screen::AlternateScreen;
+use signal_hook::SIGWINCH;
+use signal_hook::iterator::Signals;
 
-use std::cmp::max;
-use std::io::{Write, stdout, stdin, Stdout};
+use std::cmp::{max, min};
+use std::collections::{BTreeSet, VecDeque};
+use std::fs;
+use std::io::{self, Write, stdout, stdin, Stdout};
+use std::mem;
 use std::sync::Arc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
 use display::pager::collector::{LinesCollector, CollectedLines};
+use display::pager::text::{crop_line, display_width};
 
-pub struct Pager {
+const MIN_WIDTH: u16 = 20;
+/// Space for one row of the list, the error banner and three status lines
+const MIN_HEIGHT: u16 = 5;
+/// Rows below the list: the search context, the counters and the prompt
+const STATUS_HEIGHT: usize = 3;
+/// Columns taken by the cursor and the item mark before the lines
+const GUTTER_WIDTH: usize = 2;
+const FIELD_PICKER_WIDTH: usize = 50;
+/// How often the server is asked for new items in the follow mode
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(2);
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+enum PagerEvent {
+    Input(Event),
+    Resize,
+    /// Periodic event which drives the follow mode and the spinner
+    Tick,
+    /// Page of the new items requested by the follow mode in the background
+    NewPage(Page<Value>)
+}
+
+type PagerEvents = Receiver<Result<PagerEvent, io::Error>>;
+
+/// Previous search with its results and position, restored by going back
+struct HistoryEntry {
+    request: SearchRequest,
+    collector: Collector<Value>,
+    top_index: usize,
+    cursor: usize,
+    marks: BTreeSet<usize>
+}
+
+pub struct Pager<'a> {
     formatter: Arc<Formatter>,
+    lines_cache: LinesCache,
     collector: Collector<Value>,
+    client: &'a Client,
+    request: SearchRequest,
+    server_name: String,
+    keymap: Keymap,
+    history: Vec<HistoryEntry>,
     stdout: AlternateScreen<RawTerminal<Stdout>>,
+    size: (u16, u16),
     top_index: usize,
     bottom_index: usize,
     scroll_mode: ScrollMode,
-    has_cropped_item: bool
+    has_cropped_item: bool,
+    wrap_mode: WrapMode,
+    column: usize,
+    max_line_width: usize,
+    cursor: usize,
+    marks: BTreeSet<usize>,
+    detail: Option<DetailView>,
+    search: Option<Search>,
+    prompt: Option<Prompt>,
+    message: Option<String>,
+    /// Loading stops after the error until the retry
+    fetch_error: Option<String>,
+    /// Scroll offset of the help overlay when it is shown
+    help_offset: Option<usize>,
+    field_stats: FieldStats,
+    field_picker: Option<FieldPicker>,
+    /// Fields chosen in the picker, printed on exit
+    picked_fields: Option<Vec<String>>,
+    following: bool,
+    last_poll: Instant,
+    /// Results for which the new items are being requested, the page is dropped if the results are replaced meanwhile
+    polling: Option<usize>,
+    /// Changed when the displayed results are replaced by another search
+    results_id: usize,
+    /// Items appended by the follow mode which were not scrolled to yet
+    new_items: usize,
+    /// Input, resizes and ticks from the background threads
+    events: PagerEvents,
+    event_sender: Sender<Result<PagerEvent, io::Error>>,
+    /// Input received while waiting for the server, handled after the request is done
+    deferred_events: VecDeque<Result<PagerEvent, io::Error>>,
+    /// Shown in the bottom line while waiting for the server
+    spinner: Option<Spinner>,
+    /// Position where the server returned no items though the total promised more, not requested again for the window
+    stalled_at: Option<usize>
 }
 
-impl Pager {
-    pub fn new(collector: Collector<Value>, formatter: Arc<Formatter>) -> Self {
-        Pager {
+impl<'a> Pager<'a> {
+    pub fn new(
+        collector: Collector<Value>,
+        formatter: Arc<Formatter>,
+        client: &'a Client,
+        request: SearchRequest,
+        server_name: &str,
+        keymap: Keymap
+    ) -> Result<Self, RenderError> {
+        let size = termion::terminal_size()?;
+        let (event_sender, events) = Self::listen_events()?;
+        Ok(Pager {
             formatter: formatter.clone(),
+            lines_cache: LinesCache::new(formatter),
             collector,
-            stdout: AlternateScreen::from(stdout().into_raw_mode().unwrap()),
+            client,
+            request,
+            server_name: server_name.to_string(),
+            keymap,
+            history: vec![],
+            stdout: AlternateScreen::from(stdout().into_raw_mode()?),
+            size,
             top_index: 0,
             bottom_index: 0,
             scroll_mode: ScrollMode::ScrollUp,
-            has_cropped_item: false
-        }
+            has_cropped_item: false,
+            wrap_mode: WrapMode::Wrap,
+            column: 0,
+            max_line_width: 0,
+            cursor: 0,
+            marks: BTreeSet::new(),
+            detail: None,
+            search: None,
+            prompt: None,
+            message: None,
+            fetch_error: None,
+            help_offset: None,
+            field_stats: FieldStats::new(),
+            field_picker: None,
+            picked_fields: None,
+            following: false,
+            last_poll: Instant::now(),
+            polling: None,
+            results_id: 0,
+            new_items: 0,
+            events,
+            event_sender,
+            deferred_events: VecDeque::new(),
+            spinner: None,
+            stalled_at: None
+        })
     }
 
-    pub fn start(mut self) {
-        let stdin = stdin();
-
-        self.display();
-        for c in stdin.events() {
-            match c.unwrap() {
-                Event::Key(Key::Char('q')) |
-                Event::Key(Key::Ctrl('c')) => break,
-                Event::Key(Key::Up) |
-                Event::Key(Key::PageUp) |
-                Event::Mouse(MouseEvent::Press(MouseButton::WheelUp, _, _)) => {
-                    if (self.scroll_mode == ScrollMode::ScrollUp) || !self.has_cropped_item {
-                        self.top_index = max(self.top_index, 1) - 1;
+    pub fn start(mut self) -> Result<(), RenderError> {
+        self.redraw()?;
+        loop {
+            let event = match self.deferred_events.pop_front() {
+                Some(event) => event,
+                None => match self.events.recv() {
+                    Ok(event) => event,
+                    Err(_) => break
+                }
+            };
+            match event? {
+                PagerEvent::Input(event) => {
+                    if !self.handle_event(event) {
+                        break;
                     }
-                    self.scroll_mode = ScrollMode::ScrollUp;
                 },
-                Event::Key(Key::Down) |
-                Event::Key(Key::PageDown) |
-                Event::Mouse(MouseEvent::Press(MouseButton::WheelDown, _, _)) => {
-                    if (self.scroll_mode == ScrollMode::ScrollDown) || !self.has_cropped_item {
-                        self.bottom_index += 1;
+                PagerEvent::Resize => {},
+                PagerEvent::Tick => {
+                    self.poll_new_items();
+                    continue;
+                },
+                PagerEvent::NewPage(page) => {
+                    if !self.add_new_items(page) {
+                        continue;
                     }
-                    self.scroll_mode = ScrollMode::ScrollDown;
+                }
+            }
+            self.redraw()?;
+        }
+
+        let picked_fields = self.picked_fields.take();
+        // the terminal is restored when the pager is dropped
+        drop(self);
+        if let Some(fields) = picked_fields.filter(|fields| !fields.is_empty()) {
+            println!("{}", fields_argument(&fields));
+        }
+        Ok(())
+    }
+
+    /// Reads keys and terminal resizes in the background threads, the ticks are sent by the timer thread.
+    /// The returned sender is used by the spinner.
+    fn listen_events() -> Result<(Sender<Result<PagerEvent, io::Error>>, PagerEvents), RenderError> {
+        let (sender, receiver) = channel();
+        let spinner_sender = sender.clone();
+
+        let tick_sender = sender.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(TICK_INTERVAL);
+                if tick_sender.send(Ok(PagerEvent::Tick)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let signals = Signals::new([SIGWINCH])?;
+        let resize_sender = sender.clone();
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                if resize_sender.send(Ok(PagerEvent::Resize)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        thread::spawn(move || {
+            for event in stdin().events() {
+                if sender.send(event.map(PagerEvent::Input)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok((spinner_sender, receiver))
+    }
+
+    /// Sends the request in the background thread, the spinner is drawn by the ticks meanwhile.
+    /// The input is kept until the request is done.
+    fn wait_for<R, F>(&mut self, text: String, request: F) -> R where F: FnOnce() -> R + Send, R: Send {
+        self.spinner = Some(Spinner::start(text, self.event_sender.clone(), || Ok(PagerEvent::Tick)));
+        let result = thread::scope(|scope| {
+            let handle = scope.spawn(request);
+            while !handle.is_finished() {
+                match self.events.recv() {
+                    // the spinner is optional, the errors are reported by the next redraw
+                    Ok(Ok(PagerEvent::Tick)) | Ok(Ok(PagerEvent::Resize)) => { let _ = self.display(); },
+                    Ok(event) => self.deferred_events.push_back(event),
+                    Err(_) => break
+                }
+            }
+            handle.join()
+        });
+        self.spinner = None;
+        result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+
+    /// Loads the next page, returns false if nothing was loaded.
+    /// The error is kept to be shown in the banner, nothing is loaded until it is cleared by the retry.
+    fn load_next_page(&mut self) -> bool {
+        if self.fetch_error.is_some() {
+            return false;
+        }
+        let request = match self.collector.next_page() {
+            Some(request) => request,
+            None => return false
+        };
+        let text = format!("Loading... {} of {}", self.collector.from, self.total_text());
+        let page = self.wait_for(text, move || request.send());
+        match self.collector.add_page(page) {
+            Ok(0) => {
+                self.stalled_at = Some(self.collector.from);
+                false
+            },
+            Ok(_) => true,
+            Err(err) => {
+                self.fetch_error = Some(format!("Cannot load more items: {}", err));
+                false
+            }
+        }
+    }
+
+    /// Returns false when the pager should be closed
+    fn handle_event(&mut self, event: Event) -> bool {
+        self.message = None;
+        if self.prompt.is_some() {
+            self.handle_prompt(event);
+            return true;
+        }
+        let action = match event {
+            Event::Key(key) => self.keymap.action(key),
+            _ => None
+        };
+        if let Some(offset) = self.help_offset {
+            match (event, action) {
+                (Event::Key(Key::Ctrl('c')), _) => return false,
+                (_, Some(Action::CursorUp)) => self.help_offset = Some(max(offset, 1) - 1),
+                (_, Some(Action::CursorDown)) => {
+                    let lines_count = help_lines(&self.keymap).len();
+                    self.help_offset = Some(min(offset + 1, max(lines_count, 1) - 1));
                 },
-                _ => {}
+                _ => self.help_offset = None
+            }
+            return true;
+        }
+        if self.detail.is_some() {
+            if let Event::Key(Key::Ctrl('c')) = event {
+                return false;
+            }
+            self.handle_detail(event, action);
+            return true;
+        }
+        if self.field_picker.is_some() {
+            if let Event::Key(Key::Ctrl('c')) = event {
+                return false;
+            }
+            self.handle_field_picker(action);
+            return true;
+        }
+        match (event, action) {
+            // Ctrl-C always works, even if the quit action is bound to other keys
+            (Event::Key(Key::Ctrl('c')), _) => return false,
+            (Event::Mouse(MouseEvent::Press(MouseButton::WheelUp, _, _)), _) => self.scroll_up(),
+            (Event::Mouse(MouseEvent::Press(MouseButton::WheelDown, _, _)), _) => self.scroll_down(),
+            (_, Some(action)) => return self.run_action(action),
+            _ => {}
+        }
+        true
+    }
+
+    /// Returns false when the pager should be closed
+    fn run_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::Quit => return false,
+            Action::Help => self.help_offset = Some(0),
+            Action::CursorUp => self.move_cursor_up(),
+            Action::CursorDown => self.move_cursor_down(),
+            Action::PageUp => self.page_up(),
+            Action::PageDown => self.page_down(),
+            Action::HalfPageUp => self.half_page_up(),
+            Action::HalfPageDown => self.half_page_down(),
+            Action::First => self.go_to_first(),
+            Action::LastLoaded => self.go_to_last_loaded(),
+            Action::Last => self.go_to_last(),
+            Action::Command => self.prompt = Some(Prompt::Command(String::new())),
+            Action::Open => self.open_detail(),
+            Action::SearchForward => self.open_search_prompt(SearchDirection::Forward),
+            Action::SearchBackward => self.open_search_prompt(SearchDirection::Backward),
+            Action::NextMatch => self.repeat_search(false),
+            Action::PreviousMatch => self.repeat_search(true),
+            Action::ToggleWrap => self.toggle_wrap_mode(),
+            Action::ScrollLeft => self.scroll_left(),
+            Action::ScrollRight => self.scroll_right(),
+            Action::Fields => self.open_field_picker(),
+            Action::EditSearch => self.prompt = Some(Prompt::Command(self.edit_command("query"))),
+            Action::Back => self.go_back(),
+            Action::Mark => self.toggle_mark(),
+            Action::Yank => self.yank_items(None, None),
+            Action::Retry => self.retry_loading(),
+            Action::Follow => self.toggle_follow(),
+            Action::ExpandAll | Action::CollapseAll => {
+                self.message = Some("Nodes are expanded or collapsed in the item opened in the tree format".to_string());
+            },
+            Action::MoveFieldUp | Action::MoveFieldDown | Action::ClearFields => {
+                self.message = Some("Fields are moved or cleared in the field picker".to_string());
+            },
+            Action::Close => {}
+        }
+        true
+    }
+
+    fn scroll_up(&mut self) {
+        if (self.scroll_mode == ScrollMode::ScrollUp) || !self.has_cropped_item {
+            self.top_index = max(self.top_index, 1) - 1;
+        }
+        self.scroll_mode = ScrollMode::ScrollUp;
+    }
+
+    fn scroll_down(&mut self) {
+        if (self.scroll_mode == ScrollMode::ScrollDown) || !self.has_cropped_item {
+            self.bottom_index += 1;
+        }
+        self.scroll_mode = ScrollMode::ScrollDown;
+    }
+
+    /// Moves the cursor to the previous item, the list is scrolled when the item is not fully visible
+    fn move_cursor_up(&mut self) {
+        let is_top_cropped = self.has_cropped_item && self.scroll_mode == ScrollMode::ScrollDown;
+        if self.cursor == self.top_index || (self.cursor == self.top_index + 1 && is_top_cropped) {
+            self.scroll_up();
+        }
+        self.cursor = max(self.cursor, 1) - 1;
+    }
+
+    /// Moves the cursor to the next item, the list is scrolled when the item is not fully visible
+    fn move_cursor_down(&mut self) {
+        let is_bottom_cropped = self.has_cropped_item && self.scroll_mode == ScrollMode::ScrollUp;
+        let next = self.cursor + 1;
+        if next >= self.bottom_index || (next + 1 == self.bottom_index && is_bottom_cropped) {
+            self.scroll_down();
+        }
+        self.cursor = next;
+    }
+
+    fn toggle_wrap_mode(&mut self) {
+        self.wrap_mode = match self.wrap_mode {
+            WrapMode::Wrap => WrapMode::NoWrap,
+            WrapMode::NoWrap => WrapMode::Wrap
+        };
+        self.column = 0;
+    }
+
+    fn horizontal_step(&self) -> usize {
+        max(self.content_width() / 2, 1)
+    }
+
+    fn scroll_left(&mut self) {
+        if self.wrap_mode == WrapMode::Wrap {
+            self.message = Some("Lines are wrapped, press w to scroll horizontally".to_string());
+            return;
+        }
+        self.column -= min(self.column, self.horizontal_step());
+    }
+
+    fn scroll_right(&mut self) {
+        if self.wrap_mode == WrapMode::Wrap {
+            self.message = Some("Lines are wrapped, press w to scroll horizontally".to_string());
+            return;
+        }
+        let max_column = max(self.max_line_width, self.content_width()) - self.content_width();
+        self.column = min(self.column + self.horizontal_step(), max_column);
+    }
+
+    fn page_up(&mut self) {
+        let is_top_cropped = self.has_cropped_item && self.scroll_mode == ScrollMode::ScrollDown;
+        // cropped item becomes the last one on the screen, unless it takes the whole screen
+        let bottom_index = if is_top_cropped && self.bottom_index - self.top_index > 1 {
+            self.top_index + 1
+        } else {
+            self.top_index
+        };
+        if bottom_index == 0 {
+            self.go_to_first();
+            return;
+        }
+        self.bottom_index = bottom_index;
+        self.cursor = bottom_index - 1;
+        self.scroll_mode = ScrollMode::ScrollDown;
+    }
+
+    fn page_down(&mut self) {
+        let is_bottom_cropped = self.has_cropped_item && self.scroll_mode == ScrollMode::ScrollUp;
+        // cropped item becomes the first one on the screen, unless it takes the whole screen
+        self.top_index = if is_bottom_cropped {
+            max(self.bottom_index - 1, self.top_index + 1)
+        } else {
+            self.bottom_index
+        };
+        self.cursor = self.top_index;
+        self.scroll_mode = ScrollMode::ScrollUp;
+    }
+
+    /// Scrolls by half of the displayed items, the cursor keeps its place on the screen
+    fn half_page_up(&mut self) {
+        let step = max((self.bottom_index - self.top_index) / 2, 1);
+        self.top_index -= min(self.top_index, step);
+        self.cursor -= min(self.cursor, step);
+        self.scroll_mode = ScrollMode::ScrollUp;
+    }
+
+    fn half_page_down(&mut self) {
+        let step = max((self.bottom_index - self.top_index) / 2, 1);
+        self.top_index += step;
+        self.cursor += step;
+        self.scroll_mode = ScrollMode::ScrollUp;
+    }
+
+    fn go_to_first(&mut self) {
+        self.top_index = 0;
+        self.cursor = 0;
+        self.scroll_mode = ScrollMode::ScrollUp;
+    }
+
+    fn go_to_last_loaded(&mut self) {
+        self.bottom_index = self.collector.from;
+        self.cursor = max(self.collector.from, 1) - 1;
+        self.scroll_mode = ScrollMode::ScrollDown;
+    }
+
+    fn go_to_last(&mut self) {
+        if self.load_until(usize::MAX) {
+            self.go_to_last_loaded();
+        }
+    }
+
+    fn go_to(&mut self, index: usize) {
+        if !self.load_until(index) {
+            return;
+        }
+        if index >= self.collector.from {
+            self.message = Some(format!("There are only {} items", self.collector.from));
+            self.go_to_last_loaded();
+            return;
+        }
+        self.top_index = index;
+        self.cursor = index;
+        self.scroll_mode = ScrollMode::ScrollUp;
+    }
+
+    /// Loads pages until the item with the index is loaded, returns false on error
+    fn load_until(&mut self, index: usize) -> bool {
+        while self.collector.from <= index && self.collector.has_more() {
+            if !self.load_next_page() {
+                break;
+            }
+        }
+        self.fetch_error.is_none()
+    }
+
+    fn retry_loading(&mut self) {
+        if self.fetch_error.take().is_none() {
+            self.message = Some("Nothing to retry".to_string());
+        }
+    }
+
+    fn toggle_follow(&mut self) {
+        self.following = !self.following;
+        if !self.following {
+            return;
+        }
+        // new items are appended to the end only if the newest ones are sorted last
+        let ascending = matches!(self.request.sort, Some(ref sort) if !sort.descending);
+        if !ascending {
+            self.message = Some(format!("New items are appended to the end only with an ascending sort, e.g. --sort {}", DEFAULT_TIME_FIELD));
+        }
+        // the polls continue after the last loaded item, the rest of the pages are not loaded at once
+        self.go_to_last_loaded();
+        self.last_poll = Instant::now();
+    }
+
+    /// Asks the server for new items in the background when it is time to, the page is received as the event,
+    /// so the keys are handled while the server is slow
+    fn poll_new_items(&mut self) {
+        if !self.following || self.polling.is_some() || self.fetch_error.is_some() || self.prompt.is_some()
+            || self.last_poll.elapsed() < FOLLOW_INTERVAL {
+            return;
+        }
+        let request = self.collector.new_page();
+        let sender = self.event_sender.clone();
+        thread::spawn(move || {
+            let _ = sender.send(Ok(PagerEvent::NewPage(request.send())));
+        });
+        self.polling = Some(self.results_id);
+    }
+
+    /// Appends the new items polled by the follow mode, returns true if the screen should be redrawn
+    fn add_new_items(&mut self, page: Page<Value>) -> bool {
+        self.last_poll = Instant::now();
+        if self.polling.take() != Some(self.results_id) {
+            return false;
+        }
+        let pinned = self.detail.is_none() && self.help_offset.is_none() && self.bottom_index >= self.collector.from;
+        match self.collector.add_page(page) {
+            Ok(0) => false,
+            Ok(count) => {
+                if pinned {
+                    self.go_to_last_loaded();
+                } else {
+                    self.new_items += count;
+                }
+                true
+            },
+            Err(err) => {
+                self.fetch_error = Some(format!("Cannot load new items: {}", err));
+                true
+            }
+        }
+    }
+
+    fn open_detail(&mut self) {
+        match self.collector.get_loaded(self.cursor) {
+            Some(item) if self.formatter.is_tree() => self.detail = Some(DetailView::tree(item, self.cursor, self.formatter.clone())),
+            Some(item) => self.detail = Some(DetailView::new(item, self.cursor)),
+            None => self.message = Some("No item selected".to_string())
+        }
+    }
+
+    fn handle_detail(&mut self, event: Event, action: Option<Action>) {
+        let height = self.working_height();
+        let detail = match self.detail.as_mut() {
+            Some(detail) => detail,
+            None => return
+        };
+        match (event, action) {
+            (_, Some(Action::Close)) |
+            (_, Some(Action::Quit)) |
+            (_, Some(Action::Back)) => self.detail = None,
+            (_, Some(Action::CursorUp)) if detail.is_tree() => detail.move_cursor_up(height),
+            (_, Some(Action::CursorDown)) if detail.is_tree() => detail.move_cursor_down(height),
+            (_, Some(Action::Open)) => detail.toggle_node(height),
+            (_, Some(Action::ExpandAll)) => detail.expand_all(height),
+            (_, Some(Action::CollapseAll)) => detail.collapse_all(height),
+            (Event::Mouse(MouseEvent::Press(MouseButton::WheelUp, _, _)), _) |
+            (_, Some(Action::CursorUp)) => detail.scroll_up(1),
+            (Event::Mouse(MouseEvent::Press(MouseButton::WheelDown, _, _)), _) |
+            (_, Some(Action::CursorDown)) => detail.scroll_down(1, height),
+            (_, Some(Action::ScrollLeft)) => self.scroll_left(),
+            (_, Some(Action::ScrollRight)) => self.scroll_right(),
+            (_, Some(Action::ToggleWrap)) => self.toggle_wrap_mode(),
+            (_, Some(Action::PageUp)) => detail.scroll_up(height),
+            (_, Some(Action::PageDown)) => detail.scroll_down(height, height),
+            (_, Some(Action::HalfPageUp)) => detail.scroll_up(height / 2),
+            (_, Some(Action::HalfPageDown)) => detail.scroll_down(height / 2, height),
+            (_, Some(Action::First)) => detail.scroll_up(detail.offset),
+            (_, Some(Action::LastLoaded)) |
+            (_, Some(Action::Last)) => detail.scroll_to_end(height),
+            (_, Some(Action::Help)) => self.help_offset = Some(0),
+            _ => {}
+        }
+    }
+
+    fn toggle_mark(&mut self) {
+        if self.collector.get_loaded(self.cursor).is_none() {
+            self.message = Some("No item selected".to_string());
+        } else if !self.marks.remove(&self.cursor) {
+            self.marks.insert(self.cursor);
+        }
+    }
+
+    /// Items of the scope, by default the marked ones or the current one if nothing is marked
+    fn items_to_export(&self, scope: Option<ExportScope>) -> Result<Vec<Value>, String> {
+        let default_scope = if self.marks.is_empty() { ExportScope::Current } else { ExportScope::Marked };
+        let indexes: Vec<usize> = match scope.unwrap_or(default_scope) {
+            ExportScope::Marked if self.marks.is_empty() => return Err("There are no marked items".to_string()),
+            ExportScope::Marked => self.marks.iter().cloned().collect(),
+            ExportScope::Current => vec![self.cursor],
+            ExportScope::All => (0..self.collector.from).collect()
+        };
+        let items: Vec<Value> = indexes.into_iter()
+            .filter_map(|index| self.collector.get_loaded(index).cloned())
+            .collect();
+        if items.is_empty() {
+            Err("No items to export".to_string())
+        } else {
+            Ok(items)
+        }
+    }
+
+    fn write_items(&mut self, path: &str, format: Option<ExportFormat>, scope: Option<ExportScope>) {
+        let format = format.unwrap_or_else(|| ExportFormat::from_path(path));
+        self.message = Some(match self.items_to_export(scope) {
+            Ok(items) => match fs::write(expand_path(path), export_items(&items, format, &self.formatter)) {
+                Ok(_) => format!("Saved {} items to {}", items.len(), path),
+                Err(err) => format!("Cannot write {}: {}", path, err)
+            },
+            Err(message) => message
+        });
+    }
+
+    /// Copies the items as JSON by default, the terminal puts them into the clipboard
+    fn yank_items(&mut self, format: Option<ExportFormat>, scope: Option<ExportScope>) {
+        let items = match self.items_to_export(scope) {
+            Ok(items) => items,
+            Err(message) => {
+                self.message = Some(message);
+                return;
+            }
+        };
+        let text = export_items(&items, format.unwrap_or(ExportFormat::JSON), &self.formatter);
+        self.message = Some(match write!(self.stdout, "{}", osc52(&text)) {
+            Ok(_) => format!("Copied {} items to the clipboard", items.len()),
+            Err(err) => format!("Cannot copy items: {}", err)
+        });
+    }
+
+    fn open_field_picker(&mut self) {
+        if !self.formatter.uses_fields() {
+            self.message = Some("Fields are not used by the json output".to_string());
+            return;
+        }
+        let Pager { field_stats, collector, .. } = self;
+        for index in field_stats.scanned..collector.from {
+            if let Some(item) = collector.get_loaded(index) {
+                field_stats.add_item(index, item);
+            }
+        }
+        let selected = self.formatter.extractor().fields().unwrap_or(&[]);
+        self.field_picker = Some(FieldPicker::new(selected, &self.field_stats));
+    }
+
+    fn handle_field_picker(&mut self, action: Option<Action>) {
+        let picker = match self.field_picker.as_mut() {
+            Some(picker) => picker,
+            None => return
+        };
+        match action {
+            Some(Action::Close) |
+            Some(Action::Quit) |
+            Some(Action::Fields) |
+            Some(Action::Back) => self.field_picker = None,
+            Some(Action::CursorUp) => picker.move_cursor_up(),
+            Some(Action::CursorDown) => picker.move_cursor_down(),
+            Some(Action::MoveFieldUp) => {
+                picker.move_field_up();
+                self.apply_fields();
+            },
+            Some(Action::MoveFieldDown) => {
+                picker.move_field_down();
+                self.apply_fields();
+            },
+            Some(Action::Mark) | Some(Action::Open) => {
+                picker.toggle();
+                self.apply_fields();
+            },
+            Some(Action::ClearFields) => {
+                picker.clear();
+                self.apply_fields();
+            },
+            _ => {}
+        }
+    }
+
+    /// Re-renders the items with the fields selected in the picker
+    fn apply_fields(&mut self) {
+        let fields = match self.field_picker.as_ref() {
+            Some(picker) => picker.selected_fields(),
+            None => return
+        };
+        let extractor = if fields.is_empty() {
+            JSONExtractor::default()
+        } else {
+            JSONExtractor::filtered(fields.clone())
+        };
+        self.formatter = Arc::new(self.formatter.with_extractor(extractor));
+        if let Some(search) = self.search.as_mut() {
+            search.reset();
+        }
+        self.picked_fields = Some(fields);
+    }
+
+    fn open_search_prompt(&mut self, direction: SearchDirection) {
+        let (is_regex, ignore_case) = self.search.as_ref()
+            .map(|search| (search.pattern.is_regex, search.pattern.ignore_case))
+            .unwrap_or((false, false));
+        self.prompt = Some(Prompt::Search(SearchPrompt::new(direction, is_regex, ignore_case)));
+    }
+
+    fn handle_prompt(&mut self, event: Event) {
+        let mut prompt = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return
+        };
+        match (event, &mut prompt) {
+            (Event::Key(Key::Char('\n')), _) => {
+                match prompt {
+                    Prompt::Search(prompt) => self.submit_search(prompt),
+                    Prompt::Command(input) => self.run_command(&input)
+                }
+                return;
+            },
+            (Event::Key(Key::Esc), _) |
+            (Event::Key(Key::Ctrl('c')), _) => return,
+            (Event::Key(Key::Backspace), prompt) if prompt.input().is_empty() => return,
+            (Event::Key(Key::Backspace), prompt) => { prompt.input_mut().pop(); },
+            (Event::Key(Key::Ctrl('r')), Prompt::Search(prompt)) => prompt.is_regex = !prompt.is_regex,
+            (Event::Key(Key::Ctrl('t')), Prompt::Search(prompt)) => prompt.ignore_case = !prompt.ignore_case,
+            (Event::Key(Key::Char('\t')), Prompt::Command(input)) => *input = self.next_edit_command(input),
+            (Event::Key(Key::Char(c)), prompt) => prompt.input_mut().push(c),
+            _ => {}
+        }
+        self.prompt = Some(prompt);
+    }
+
+    fn run_command(&mut self, input: &str) {
+        match PagerCommand::parse(input) {
+            Ok(PagerCommand::GoTo(index)) => self.go_to(index),
+            Ok(PagerCommand::Query(query)) => self.run_request(SearchRequest { query, ..self.request.clone() }),
+            Ok(PagerCommand::Index(index)) => self.run_request(SearchRequest { index, ..self.request.clone() }),
+            Ok(PagerCommand::Range(from, to)) => {
+                let range = if from.is_some() || to.is_some() {
+                    let field = self.request.range.as_ref()
+                        .map(|range| range.field.clone())
+                        .unwrap_or_else(|| DEFAULT_TIME_FIELD.to_string());
+                    Some(TimeRange { field, from, to })
+                } else {
+                    None
+                };
+                self.run_request(SearchRequest { range, ..self.request.clone() })
+            },
+            Ok(PagerCommand::Back) => self.go_back(),
+            Ok(PagerCommand::Write { path, format, scope }) => self.write_items(&path, format, scope),
+            Ok(PagerCommand::Yank { format, scope }) => self.yank_items(format, scope),
+            Err(message) => self.message = Some(message)
+        }
+    }
+
+    /// Command editing the part of the current request: `query`, `index` or `range`
+    fn edit_command(&self, name: &str) -> String {
+        match name {
+            "index" => format!("index {}", self.request.index),
+            "range" => {
+                let bound = |bound: Option<&String>| bound.cloned().unwrap_or_else(|| "-".to_string());
+                let range = self.request.range.as_ref();
+                format!("range {} {}", bound(range.and_then(|r| r.from.as_ref())), bound(range.and_then(|r| r.to.as_ref())))
+            },
+            _ => format!("query {}", self.request.query)
+        }
+    }
+
+    /// Tab switches the edited part of the request
+    fn next_edit_command(&self, input: &str) -> String {
+        let next = match input.split_whitespace().next() {
+            Some("query") => "index",
+            Some("index") => "range",
+            _ => "query"
+        };
+        self.edit_command(next)
+    }
+
+    /// Executes the request and shows its results, the current ones are kept in the history
+    fn run_request(&mut self, request: SearchRequest) {
+        let client = self.client;
+        let result = self.wait_for("Searching...".to_string(), || client.execute(&request));
+        match result {
+            Ok(collector) => {
+                let entry = HistoryEntry {
+                    request: mem::replace(&mut self.request, request),
+                    collector: mem::replace(&mut self.collector, collector),
+                    top_index: self.top_index,
+                    cursor: self.cursor,
+                    marks: mem::take(&mut self.marks)
+                };
+                self.history.push(entry);
+                self.reset_results(0, 0);
+            },
+            Err(err) => self.message = Some(format!("Cannot execute the search: {}", err))
+        }
+    }
+
+    fn go_back(&mut self) {
+        match self.history.pop() {
+            Some(entry) => {
+                self.request = entry.request;
+                self.collector = entry.collector;
+                self.marks = entry.marks;
+                self.reset_results(entry.top_index, entry.cursor);
+            },
+            None => self.message = Some("There are no previous searches".to_string())
+        }
+    }
+
+    /// Resets the state which depends on the displayed results
+    fn reset_results(&mut self, top_index: usize, cursor: usize) {
+        self.results_id += 1;
+        self.top_index = top_index;
+        self.bottom_index = top_index;
+        self.cursor = cursor;
+        self.scroll_mode = ScrollMode::ScrollUp;
+        self.detail = None;
+        self.lines_cache.clear();
+        self.fetch_error = None;
+        self.new_items = 0;
+        self.stalled_at = None;
+        self.field_stats = FieldStats::new();
+        if let Some(search) = self.search.as_mut() {
+            search.reset();
+        }
+    }
+
+    fn submit_search(&mut self, prompt: SearchPrompt) {
+        // empty input repeats the previous search in the new direction, like less does
+        if prompt.input.is_empty() {
+            if let Some(search) = self.search.as_mut() {
+                search.direction = prompt.direction;
+            }
+            self.repeat_search(false);
+            return;
+        }
+
+        match SearchPattern::new(&prompt.input, prompt.is_regex, prompt.ignore_case) {
+            Ok(pattern) => {
+                self.search = Some(Search::new(pattern, prompt.direction));
+                self.jump_to_match(prompt.direction, true);
+            },
+            Err(err) => {
+                self.message = Some(format!("Invalid pattern: {}", err));
+            }
+        }
+    }
+
+    fn repeat_search(&mut self, reverse: bool) {
+        let direction = match self.search.as_ref() {
+            Some(search) if reverse => search.direction.reversed(),
+            Some(search) => search.direction,
+            None => {
+                self.message = Some("No previous search pattern".to_string());
+                return;
+            }
+        };
+        self.jump_to_match(direction, false);
+    }
+
+    fn jump_to_match(&mut self, direction: SearchDirection, inclusive: bool) {
+        match self.find_match(direction, inclusive) {
+            Some(index) => {
+                self.top_index = index;
+                self.cursor = index;
+                self.scroll_mode = ScrollMode::ScrollUp;
+            },
+            None => {
+                if self.message.is_none() && self.fetch_error.is_none() {
+                    let text = self.search.as_ref().map(|s| s.pattern.text.clone()).unwrap_or_default();
+                    self.message = Some(format!("Pattern not found: {}", text));
+                }
             }
-            self.display();
         }
     }
 
-    fn display(&mut self) {
-        let (_, height) = termion::terminal_size().unwrap();
-        let working_height = (height - 2) as usize;
+    fn find_match(&mut self, direction: SearchDirection, inclusive: bool) -> Option<usize> {
+        loop {
+            self.scan_loaded_items();
+            let found = self.search.as_ref()?.find(self.top_index, direction, inclusive);
+            if found.is_some() || direction == SearchDirection::Backward || !self.collector.has_more() {
+                return found;
+            }
 
-        let lines = self.get_lines(working_height);
+            if !self.load_next_page() {
+                return None;
+            }
+        }
+    }
+
+    fn scan_loaded_items(&mut self) {
+        let Pager { search, collector, formatter, .. } = self;
+        if let Some(search) = search.as_mut() {
+            for index in search.scanned..collector.from {
+                if let Some(item) = collector.get_loaded(index) {
+                    search.add_item(index, &formatter.format(item, index));
+                }
+            }
+        }
+    }
+
+    /// Total of the search, `≥ N` if the server stopped counting
+    fn total_text(&self) -> String {
+        match self.collector.total_relation {
+            TotalRelation::GreaterOrEqual => format!("≥ {}", self.collector.total),
+            TotalRelation::Equal => self.collector.total.to_string()
+        }
+    }
+
+    fn working_height(&self) -> usize {
+        let banner_height = if self.fetch_error.is_some() { 1 } else { 0 };
+        max(self.size.1, MIN_HEIGHT) as usize - STATUS_HEIGHT - banner_height
+    }
+
+    fn content_width(&self) -> usize {
+        max(self.size.0, MIN_WIDTH) as usize - GUTTER_WIDTH
+    }
+
+    /// Draws the screen, the pages missing in the window are loaded and the screen is drawn again
+    fn redraw(&mut self) -> Result<(), RenderError> {
+        while self.display()? {
+            if !self.load_next_page() {
+                self.display()?;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the screen, returns true if the window needs the items which are not loaded yet.
+    /// Only the bottom line is drawn then, it shows the spinner while the page is loading.
+    fn display(&mut self) -> Result<bool, RenderError> {
+        self.size = termion::terminal_size()?;
+        let (width, height) = self.size;
+        if width < MIN_WIDTH || height < MIN_HEIGHT {
+            self.clear()?;
+            write!(self.stdout, "{}", &"Terminal is too small"[..min(width as usize, 21)])?;
+            self.stdout.flush()?;
+            return Ok(false);
+        }
+
+        if let Some(offset) = self.help_offset {
+            return self.display_help(offset).map(|_| false);
+        }
+        if self.detail.is_some() {
+            return self.display_detail().map(|_| false);
+        }
+
+        let working_height = self.working_height();
+
+        let (mut lines, is_missing_items) = self.get_lines(working_height);
+        if is_missing_items {
+            self.print_bottom_line()?;
+            self.stdout.flush()?;
+            return Ok(true);
+        }
+        // keep the window inside of the available items
+        if self.scroll_mode == ScrollMode::ScrollUp && lines.items_count == 0 && self.top_index > 0 {
+            self.go_to_last_loaded();
+            lines = self.get_lines(working_height).0;
+        } else if self.scroll_mode == ScrollMode::ScrollDown && self.bottom_index > self.collector.from {
+            self.bottom_index = self.collector.from;
+        }
 
         self.has_cropped_item = lines.has_cropped_items;
         match self.scroll_mode {
@@ -82,56 +1016,387 @@ impl Pager {
             }
         }
 
-        self.clear();
-        self.print_lines(lines.lines);
+        self.cursor = min(max(self.cursor, self.top_index), max(self.bottom_index, 1) - 1);
+        if self.bottom_index >= self.collector.from {
+            self.new_items = 0;
+        }
+        self.max_line_width = lines.lines.iter().map(|line| display_width(line)).max().unwrap_or(0);
+
+        self.clear()?;
+        self.print_lines(lines.lines, lines.item_indexes)?;
 
-        write!(self.stdout, "{}Loaded {} from {}, displayed {}-{} ({} items)",
-               termion::cursor::Goto(1, height - 1),
+        self.scan_loaded_items();
+        let search_status = self.search.as_ref()
+            .map(|search| {
+                let position = search.position(self.top_index)
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                let more = if self.collector.has_more() { "+" } else { "" };
+                format!(", {}{} {}/{}{}",
+                        search.direction.prompt_char(),
+                        search.pattern.describe(),
+                        position,
+                        search.matches.len(),
+                        more
+                )
+            })
+            .unwrap_or_default();
+
+        let wrap_status = match self.wrap_mode {
+            WrapMode::Wrap => "wrap".to_string(),
+            WrapMode::NoWrap => format!("no wrap, column {}", self.column)
+        };
+        let marks_status = if self.marks.is_empty() {
+            String::new()
+        } else {
+            format!(", marked {}", self.marks.len())
+        };
+        let follow_status = match (self.following, self.new_items) {
+            (true, 0) => format!(", following every {} s", FOLLOW_INTERVAL.as_secs()),
+            (true, count) => format!(", following every {} s, {}", FOLLOW_INTERVAL.as_secs(), format!("{} new", count).yellow().bold()),
+            (false, 0) => String::new(),
+            (false, count) => format!(", {}", format!("{} new", count).yellow().bold())
+        };
+        self.print_context_lines()?;
+        let status_line = format!("Loaded {} from {}, displayed {}-{} ({} items), selected {}{}{}, {}{}",
                self.collector.from,
-               self.collector.total,
+               self.total_text(),
                self.top_index,
                self.bottom_index,
-               lines.items_count
-        ).unwrap();
+               lines.items_count,
+               self.cursor,
+               marks_status,
+               follow_status,
+               wrap_status,
+               search_status
+        );
+        write!(self.stdout, "{}{}",
+               termion::cursor::Goto(1, height - 1),
+               crop_line(&status_line, 0, width as usize)
+        )?;
+
+        self.print_field_picker()?;
+        self.print_bottom_line()?;
+        self.stdout.flush()?;
+        Ok(false)
+    }
+
+    /// Prompt, spinner, message or key hints
+    fn print_bottom_line(&mut self) -> Result<(), RenderError> {
+        let (width, height) = self.size;
+        let bottom_line = match (&self.prompt, &self.spinner, &self.message) {
+            (Some(prompt), _, _) => prompt.render(),
+            (None, Some(spinner), _) => spinner.render(),
+            (None, None, Some(message)) => message.clone(),
+            (None, None, None) if self.field_picker.is_some() => {
+                let hints: Vec<String> = [
+                    (Action::Open, "toggle field"),
+                    (Action::MoveFieldUp, "move field up"),
+                    (Action::MoveFieldDown, "move field down"),
+                    (Action::ClearFields, "show all fields"),
+                    (Action::Close, "close")
+                ].iter()
+                    .filter_map(|&(action, text)| self.keymap.hint(action, text))
+                    .collect();
+                hints.join(", ")
+            },
+            (None, None, None) => {
+                let hints: Vec<String> = [
+                    (Action::Quit, "exit"),
+                    (Action::Help, "help"),
+                    (Action::Open, "open"),
+                    (Action::SearchForward, "search"),
+                    (Action::EditSearch, "edit search"),
+                    (Action::Fields, "fields"),
+                    (Action::Mark, "mark")
+                ].iter()
+                    .filter_map(|&(action, text)| self.keymap.hint(action, text))
+                    .collect();
+                format!("{}, :w <file>: save", hints.join(", "))
+            }
+        };
+        write!(self.stdout, "{}{}{}",
+               termion::cursor::Goto(1, height),
+               termion::clear::CurrentLine,
+               crop_line(&bottom_line, 0, width as usize)
+        )?;
+        if self.prompt.is_some() {
+            write!(self.stdout, "{}", termion::cursor::Show)?;
+        }
+        Ok(())
+    }
+
+    fn display_detail(&mut self) -> Result<(), RenderError> {
+        let (width, height) = self.size;
+        let working_height = self.working_height();
+        let total = self.total_text();
+
+        self.clear()?;
+        let mut is_tree = false;
+        if let Some(detail) = self.detail.as_mut() {
+            is_tree = detail.is_tree();
+            // the tree has the gutter with the cursor like the list
+            let gutter_width = if is_tree { GUTTER_WIDTH } else { 0 };
+            let content_width = width as usize - gutter_width;
+            detail.layout(content_width, self.wrap_mode);
+            self.max_line_width = detail.visible_rows(working_height).iter()
+                .map(|row| display_width(row))
+                .max()
+                .unwrap_or(0);
+            for (index, row) in detail.visible_rows(working_height).iter().enumerate() {
+                let row = match self.wrap_mode {
+                    WrapMode::Wrap => row.clone(),
+                    WrapMode::NoWrap => crop_line(row, self.column, content_width)
+                };
+                let gutter = match (is_tree, detail.is_cursor_row(detail.offset + index)) {
+                    (false, _) => "".normal(),
+                    (true, true) => "▌ ".blue().bold(),
+                    (true, false) => "  ".normal()
+                };
+                write!(self.stdout, "{}{}{}",
+                       termion::cursor::Goto(1, (index + 1) as u16),
+                       gutter,
+                       row
+                )?;
+            }
+
+            let node_status = detail.cursor_path()
+                .map(|path| format!(", node {}", path))
+                .unwrap_or_default();
+            let status_line = format!("Item {} of {}, rows {}-{} of {}{}",
+                   detail.item_index,
+                   total,
+                   detail.offset + 1,
+                   min(detail.offset + working_height, detail.rows.len()),
+                   detail.rows.len(),
+                   node_status
+            );
+            write!(self.stdout, "{}{}",
+                   termion::cursor::Goto(1, height - 1),
+                   crop_line(&status_line, 0, width as usize)
+            )?;
+        }
 
-        write!(self.stdout, "{}Press q to exit, ↑/↓ to navigate",
-               termion::cursor::Goto(1, height)
-        ).unwrap();
+        self.print_context_lines()?;
+        let hints: &[(Action, &str)] = if is_tree {
+            &[
+                (Action::Close, "back"),
+                (Action::Open, "expand/collapse"),
+                (Action::ExpandAll, "expand all"),
+                (Action::CollapseAll, "collapse all"),
+                (Action::Help, "help")
+            ]
+        } else {
+            &[(Action::Close, "back"), (Action::CursorUp, "scroll up"), (Action::CursorDown, "scroll down"), (Action::Help, "help")]
+        };
+        let hint: Vec<String> = hints.iter()
+            .filter_map(|&(action, text)| self.keymap.hint(action, text))
+            .collect();
+        write!(self.stdout, "{}{}",
+               termion::cursor::Goto(1, height),
+               crop_line(&hint.join(", "), 0, width as usize)
+        )?;
 
-        self.stdout.flush().unwrap();
+        self.stdout.flush()?;
+        Ok(())
     }
 
-    fn clear(&mut self) {
+    fn display_help(&mut self, offset: usize) -> Result<(), RenderError> {
+        let (width, height) = self.size;
+        let rows = height as usize - 1;
+
+        self.clear()?;
+        let lines = help_lines(&self.keymap);
+        for (index, line) in lines.iter().skip(offset).take(rows).enumerate() {
+            write!(self.stdout, "{}{}",
+                   termion::cursor::Goto(1, (index + 1) as u16),
+                   crop_line(line, 0, width as usize)
+            )?;
+        }
+        let bottom_line = format!("Keys {}-{} of {}, ↑/↓: scroll, any other key: close",
+                                  offset + 1,
+                                  min(offset + rows, lines.len()),
+                                  lines.len()
+        );
+        write!(self.stdout, "{}{}",
+               termion::cursor::Goto(1, height),
+               crop_line(&bottom_line, 0, width as usize).bold()
+        )?;
+
+        self.stdout.flush()?;
+        Ok(())
+    }
+
+    /// Prints the error banner, if any, and the line with the server, the request and the latency
+    fn print_context_lines(&mut self) -> Result<(), RenderError> {
+        let (width, height) = self.size;
+        let width = width as usize;
+        let context_row = height - STATUS_HEIGHT as u16 + 1;
+
+        if let Some(error) = &self.fetch_error {
+            let banner = pad_line(&format!(" {}, r: retry", error), width);
+            write!(self.stdout, "{}{}",
+                   termion::cursor::Goto(1, context_row - 1),
+                   banner.white().on_red().bold()
+            )?;
+        }
+
+        let range = self.request.range.as_ref()
+            .map(|range| format!("{} {}..{}",
+                                 range.field,
+                                 range.from.as_deref().unwrap_or("*"),
+                                 range.to.as_deref().unwrap_or("*")
+            ))
+            .unwrap_or_else(|| "all time".to_string());
+        let sort = self.request.sort.as_ref()
+            .map(|sort| format!("{} {}", sort.field, if sort.descending { "desc" } else { "asc" }))
+            .unwrap_or_else(|| "relevance".to_string());
+        let latency = self.collector.latency
+            .map(|latency| format!("{} ms", latency.as_millis()))
+            .unwrap_or_else(|| "-".to_string());
+        let context_line = format!(" {} │ {} │ {} │ sort: {} │ range: {} │ {}",
+                                   self.server_name,
+                                   self.request.index,
+                                   self.request.query,
+                                   sort,
+                                   range,
+                                   latency
+        );
+        write!(self.stdout, "{}{}",
+               termion::cursor::Goto(1, context_row),
+               pad_line(&context_line, width).reversed()
+        )?;
+        Ok(())
+    }
+
+    /// Draws the picker over the right part of the list
+    fn print_field_picker(&mut self) -> Result<(), RenderError> {
+        let picker = match self.field_picker.as_ref() {
+            Some(picker) => picker,
+            None => return Ok(())
+        };
+        let width = self.size.0 as usize;
+        let box_width = min(FIELD_PICKER_WIDTH, width);
+        let column = (width - box_width + 1) as u16;
+        // one row is taken by the title
+        let rows = self.working_height() - 1;
+        let first = if picker.cursor >= rows { picker.cursor + 1 - rows } else { 0 };
+
+        let selected = picker.selected_fields();
+        let title = if selected.is_empty() { "Fields: all".to_string() } else { format!("Fields: {}", fields_argument(&selected)) };
+        let mut box_lines = vec![format!("{}", pad_line(&title, box_width - 1).bold().reversed())];
+        box_lines.extend(
+            picker.entries.iter()
+                .enumerate()
+                .skip(first)
+                .take(rows)
+                .map(|(index, entry)| {
+                    let mark = if entry.selected { "[x]" } else { "[ ]" };
+                    let text = pad_line(&format!("{} {} ({})", mark, entry.name, entry.count), box_width - 1);
+                    if index == picker.cursor { text.reversed().to_string() } else { text }
+                })
+        );
+        if picker.entries.is_empty() {
+            box_lines.push(pad_line("No fields in the loaded items", box_width - 1));
+        }
+        while box_lines.len() < rows + 1 {
+            box_lines.push(pad_line("", box_width - 1));
+        }
+
+        for (index, line) in box_lines.iter().enumerate() {
+            write!(self.stdout, "{}{}{}{}",
+                   termion::cursor::Goto(column, (index + 1) as u16),
+                   termion::style::Reset,
+                   "│".blue(),
+                   line
+            )?;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), RenderError> {
         write!(self.stdout,
                "{}{}{}",
                termion::clear::All,
                termion::cursor::Goto(1, 1),
                termion::cursor::Hide
-        ).unwrap();
+        )?;
+        Ok(())
     }
 
-    fn get_lines(&mut self, limit: usize) -> CollectedLines {
-        match self.scroll_mode {
+    /// Lines of the loaded items in the window, true if the window needs more items which can be loaded
+    fn get_lines(&mut self, limit: usize) -> (CollectedLines, bool) {
+        let wrap_width = match self.wrap_mode {
+            WrapMode::Wrap => Some(self.content_width()),
+            WrapMode::NoWrap => None
+        };
+        self.lines_cache.configure(&self.formatter, wrap_width);
+
+        let can_load = self.fetch_error.is_none() && self.stalled_at != Some(self.collector.from);
+        let mut is_missing_items = false;
+        let Pager { lines_cache, collector, .. } = self;
+        // only the items of the window are formatted, the rest of the lines are taken from the cache
+        let item_lines = |index: usize| {
+            if index >= collector.from && collector.has_more() && can_load {
+                is_missing_items = true;
+            }
+            collector.get_loaded(index).map(|item| lines_cache.lines(index, item))
+        };
+
+        let lines_collector = LinesCollector::new();
+        let lines = match self.scroll_mode {
             ScrollMode::ScrollUp => {
-                LinesCollector::new(self.formatter.clone())
+                lines_collector
                     .skip_items(self.top_index)
                     .take_lines(limit)
             },
             ScrollMode::ScrollDown => {
-                LinesCollector::new(self.formatter.clone())
+                lines_collector
                     .take_items(self.bottom_index)
                     .take_last_lines(limit)
             }
-        }.collect(self.collector.iter())
+        }.collect(item_lines);
+        (lines, is_missing_items)
     }
 
-    fn print_lines(&mut self, lines: Vec<String>) {
-        lines.iter().enumerate().for_each(|(index, line)| {
-            write!(self.stdout, "{}{}",
+    fn print_lines(&mut self, lines: Vec<String>, item_indexes: Vec<usize>) -> Result<(), RenderError> {
+        let mut previous_item = None;
+        for (index, (line, item_index)) in lines.iter().zip(item_indexes).enumerate() {
+            let line = match &self.search {
+                Some(search) => search.pattern.highlight(line),
+                None => line.clone()
+            };
+            let line = match self.wrap_mode {
+                WrapMode::Wrap => line,
+                WrapMode::NoWrap => crop_line(&line, self.column, self.content_width())
+            };
+            let gutter = if item_index == self.cursor { "▌".blue().bold() } else { " ".normal() };
+            // the mark is shown only at the first line of the item
+            let is_marked = previous_item != Some(item_index) && self.marks.contains(&item_index);
+            let mark = if is_marked { "*".yellow().bold() } else { " ".normal() };
+            previous_item = Some(item_index);
+            write!(self.stdout, "{}{}{}{}",
                    termion::cursor::Goto(1, (index + 1) as u16),
+                   gutter,
+                   mark,
                    line
-            ).unwrap();
-        });
+            )?;
+        }
+        Ok(())
     }
+}
 
-}
\ No newline at end of file
+/// Crops or pads the line with spaces to exactly `width` columns
+fn pad_line(line: &str, width: usize) -> String {
+    let line = crop_line(line, 0, width);
+    let padding = width - min(display_width(&line), width);
+    format!("{}{}", line, " ".repeat(padding))
+}
+
+impl<'a> Drop for Pager<'a> {
+    fn drop(&mut self) {
+        // raw mode and the alternate screen are restored by their own destructors
+        let _ = write!(self.stdout, "{}", termion::cursor::Show);
+        let _ = self.stdout.flush();
+    }
+}
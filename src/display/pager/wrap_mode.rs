@@ -0,0 +1,5 @@
+#[derive(PartialEq, Clone, Copy)]
+pub enum WrapMode {
+    Wrap,
+    NoWrap
+}
\ No newline at end of file
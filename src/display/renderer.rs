@@ -1,13 +1,29 @@
-use super::{OutputFormat, JSONExtractor, Pager};
-use crate::client::Collector;
+use super::{OutputFormat, JSONExtractor, Pager, Keymap};
+use crate::client::{Client, Collector, FetcherError, SearchRequest};
 use crate::display::{Formatter};
 
 use serde_json::Value;
 
+use std::io::{Write, stdout};
 use std::sync::Arc;
 
 pub trait Renderer {
-    fn render(&mut self, collector: Collector<Value>);
+    /// The client and the request allow to re-run the search with changed parameters
+    fn render(&mut self, collector: Collector<Value>, client: &Client, request: &SearchRequest) -> Result<(), RenderError>;
+}
+
+#[derive(Debug, Fail)]
+pub enum RenderError {
+    #[fail(display = "cannot write to the terminal: {}", inner)]
+    IOError { inner: std::io::Error },
+    #[fail(display = "results are incomplete, {} of {} items were loaded: {}", loaded, total, inner)]
+    Incomplete { loaded: usize, total: usize, inner: FetcherError }
+}
+
+impl From<::std::io::Error> for RenderError {
+    fn from(error: ::std::io::Error) -> Self {
+        RenderError::IOError { inner: error }
+    }
 }
 
 pub struct SimpleRenderer {
@@ -23,31 +39,44 @@ impl SimpleRenderer {
 }
 
 impl Renderer for SimpleRenderer {
-    fn render(&mut self, mut collector: Collector<Value>) {
-        collector.iter().enumerate()
-            .for_each(|(index, item)| {
-                print!("{}", self.formatter.format(&item, index));
-            });
+    fn render(&mut self, mut collector: Collector<Value>, _: &Client, _: &SearchRequest) -> Result<(), RenderError> {
+        let stdout = stdout();
+        let mut stdout = stdout.lock();
+        for (index, item) in collector.iter().enumerate() {
+            write!(stdout, "{}", self.formatter.format(&item, index))?;
+        }
+        match collector.failure.take() {
+            Some(err) => Err(RenderError::Incomplete { loaded: collector.from, total: collector.total, inner: err }),
+            None => Ok(())
+        }
     }
 }
 
 pub struct PagedRenderer {
-    formatter: Arc<Formatter>
+    formatter: Arc<Formatter>,
+    server_name: String,
+    keymap: Keymap
 }
 
 impl PagedRenderer {
-    pub fn new(format: OutputFormat, extractor: JSONExtractor) -> Self {
+    pub fn new(format: OutputFormat, extractor: JSONExtractor, server_name: &str, keymap: Keymap) -> Self {
         Self {
-            formatter: Arc::new(Formatter::new(format, extractor))
+            formatter: Arc::new(Formatter::new(format, extractor)),
+            server_name: server_name.to_string(),
+            keymap
         }
     }
 }
 
 impl Renderer for PagedRenderer {
-    fn render(&mut self, collector: Collector<Value>) {
+    fn render(&mut self, collector: Collector<Value>, client: &Client, request: &SearchRequest) -> Result<(), RenderError> {
         Pager::new(
             collector,
-            self.formatter.clone()
-        ).start()
+            self.formatter.clone(),
+            client,
+            request.clone(),
+            &self.server_name,
+            self.keymap.clone()
+        )?.start()
     }
 }
\ No newline at end of file
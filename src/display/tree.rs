@@ -0,0 +1,188 @@
+//! Indented tree of the nested objects and arrays with guides
+
+use serde_json::{Map, Value};
+use colored::*;
+
+use std::collections::BTreeSet;
+
+/// One line of the tree
+#[derive(Debug, PartialEq)]
+pub struct TreeRow {
+    /// Keys and array indexes from the root joined with dots, like the fields of `JSONExtractor`
+    pub path: String,
+    pub line: String,
+    /// The node has children which can be collapsed
+    pub is_container: bool
+}
+
+/// Renders the value as a tree, the children of the collapsed paths are replaced by their count
+pub fn tree_rows(value: &Value, collapsed: &BTreeSet<String>) -> Vec<TreeRow> {
+    let mut rows = vec![];
+    if children(value).is_empty() {
+        rows.push(TreeRow {
+            path: String::new(),
+            line: scalar_string(value),
+            is_container: false
+        });
+    } else {
+        write_children(value, "", "", collapsed, &mut rows);
+    }
+    rows
+}
+
+/// Keeps only the subtrees of the fields, array indexes of the kept nodes become object keys
+pub fn select_subtrees(value: &Value, fields: &[String]) -> Value {
+    let mut selected = Map::new();
+    for field in fields {
+        let path: Vec<&str> = field.split('.').collect();
+        let pointer: String = path.iter()
+            .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+            .collect();
+        if let Some(subtree) = value.pointer(&pointer) {
+            insert_subtree(&mut selected, &path, subtree);
+        }
+    }
+    Value::Object(selected)
+}
+
+fn insert_subtree(target: &mut Map<String, Value>, path: &[&str], subtree: &Value) {
+    match path.split_first() {
+        Some((key, [])) => {
+            target.insert(key.to_string(), subtree.clone());
+        },
+        Some((key, rest)) => {
+            let child = target.entry(key.to_string()).or_insert_with(|| Value::Object(Map::new()));
+            // a non-object child is the whole subtree selected by another field
+            if let Value::Object(child) = child {
+                insert_subtree(child, rest, subtree);
+            }
+        },
+        None => {}
+    }
+}
+
+fn children(value: &Value) -> Vec<(String, &Value)> {
+    match value {
+        Value::Object(object) => object.iter().map(|(key, value)| (key.clone(), value)).collect(),
+        Value::Array(array) => array.iter().enumerate().map(|(index, value)| (index.to_string(), value)).collect(),
+        _ => vec![]
+    }
+}
+
+fn write_children(value: &Value, path: &str, prefix: &str, collapsed: &BTreeSet<String>, rows: &mut Vec<TreeRow>) {
+    let children = children(value);
+    for (position, (key, child)) in children.iter().enumerate() {
+        let is_last = position + 1 == children.len();
+        let guide = if is_last { "└── " } else { "├── " };
+        let child_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+        let is_container = !self::children(child).is_empty();
+        let is_collapsed = is_container && collapsed.contains(&child_path);
+        let label = key.green().bold();
+        let line = if is_collapsed {
+            format!("{}{}{}: {}", prefix, guide, label, summary(child).dimmed())
+        } else if is_container {
+            format!("{}{}{}", prefix, guide, label)
+        } else {
+            format!("{}{}{}: {}", prefix, guide, label, scalar_string(child))
+        };
+        rows.push(TreeRow {
+            path: child_path.clone(),
+            line,
+            is_container
+        });
+        if is_container && !is_collapsed {
+            let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+            write_children(child, &child_path, &child_prefix, collapsed, rows);
+        }
+    }
+}
+
+fn summary(value: &Value) -> String {
+    match value {
+        Value::Array(array) => format!("[…{}]", array.len()),
+        Value::Object(object) => format!("{{…{}}}", object.len()),
+        _ => scalar_string(value)
+    }
+}
+
+/// Strings are shown without quotes, line breaks are escaped to keep the guides aligned
+fn scalar_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.replace('\n', "\\n").replace('\t', "\\t"),
+        other => other.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::pager::text::strip_ansi;
+
+    fn lines(value: &Value, collapsed: &[&str]) -> Vec<String> {
+        let collapsed = collapsed.iter().map(ToString::to_string).collect();
+        tree_rows(value, &collapsed).iter().map(|row| strip_ansi(&row.line)).collect()
+    }
+
+    fn item() -> Value {
+        json!({
+            "root": {
+                "arr": [{ "value": 1 }, 2],
+                "obj": { "strKey": "a\nb" }
+            },
+            "level": "Error"
+        })
+    }
+
+    #[test]
+    fn it_should_render_nested_values_with_guides() {
+        assert_eq!(
+            vec![
+                "├── level: Error",
+                "└── root",
+                "    ├── arr",
+                "    │   ├── 0",
+                "    │   │   └── value: 1",
+                "    │   └── 1: 2",
+                "    └── obj",
+                "        └── strKey: a\\nb"
+            ],
+            lines(&item(), &[])
+        );
+    }
+
+    #[test]
+    fn it_should_replace_children_of_collapsed_nodes_with_count() {
+        assert_eq!(
+            vec![
+                "├── level: Error",
+                "└── root",
+                "    ├── arr: […2]",
+                "    └── obj",
+                "        └── strKey: a\\nb"
+            ],
+            lines(&item(), &["root.arr", "root.obj.strKey"])
+        );
+    }
+
+    #[test]
+    fn it_should_render_scalars_and_empty_containers_as_one_line() {
+        assert_eq!(vec!["1"], lines(&json!(1), &[]));
+        assert_eq!(vec!["{}"], lines(&json!({}), &[]));
+        assert_eq!(vec!["└── a: []"], lines(&json!({ "a": [] }), &[]));
+    }
+
+    #[test]
+    fn it_should_select_subtrees_of_fields() {
+        let fields = vec!["root.obj".to_string(), "root.arr.0.value".to_string(), "missing".to_string()];
+        assert_eq!(
+            json!({ "root": { "obj": { "strKey": "a\nb" }, "arr": { "0": { "value": 1 } } } }),
+            select_subtrees(&item(), &fields)
+        );
+    }
+
+    #[test]
+    fn selected_parent_should_include_selected_children() {
+        let fields = vec!["root.arr".to_string(), "root.arr.0.value".to_string()];
+        assert_eq!(json!({ "root": { "arr": [{ "value": 1 }, 2] } }), select_subtrees(&item(), &fields));
+    }
+}
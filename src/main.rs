@@ -1,6 +1,5 @@
 #[macro_use]
 extern crate clap;
-extern crate elastic;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
@@ -20,6 +19,14 @@ extern crate failure;
 extern crate keyring;
 extern crate rpassword;
 extern crate termion;
+extern crate regex;
+extern crate signal_hook;
+extern crate unicode_width;
+extern crate openssl;
+extern crate rand;
+extern crate chrono;
+extern crate flate2;
+extern crate glob;
 extern crate core;
 
 mod config;